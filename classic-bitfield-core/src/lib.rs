@@ -0,0 +1,175 @@
+//! Runtime support for types generated by `classic-bitfield`'s
+//! `#[bitfield_enum]` attribute. Use it through the `classic-bitfield` crate,
+//! which re-exports everything here.
+
+use core::{
+    fmt::Debug,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not},
+};
+
+/// Implemented by every type generated by `#[bitfield_enum]`, so that generic
+/// code (e.g. `fn describe<F: BitfieldEnum>(flags: F)`) can work with any of
+/// them.
+pub trait BitfieldEnum:
+    Copy
+    + Eq
+    + Debug
+    + BitAnd<Output = Self>
+    + BitAndAssign
+    + BitOr<Output = Self>
+    + BitOrAssign
+    + Not<Output = Self>
+    + 'static
+{
+    /// The underlying representation, e.g. `u8` for `#[bitfield_enum(as u8)]`.
+    type Repr: Copy;
+
+    /// The name of each variant along with the corresponding value.
+    fn variant_pairs() -> &'static [(&'static str, Self)];
+
+    /// The raw bits of this value.
+    fn bits(self) -> Self::Repr;
+
+    /// A value from raw bits, keeping any bits not defined by a variant.
+    fn from_bits_retain(bits: Self::Repr) -> Self;
+
+    /// A value with no bits set.
+    fn empty() -> Self;
+
+    /// A value with every named variant set.
+    fn all() -> Self {
+        Self::variant_pairs()
+            .iter()
+            .fold(Self::empty(), |acc, (_, value)| acc | *value)
+    }
+
+    /// A value from raw bits, or `None` if any bits not defined by a variant
+    /// are set.
+    fn from_bits(bits: Self::Repr) -> Option<Self> {
+        let value = Self::from_bits_retain(bits);
+        if value.difference(Self::all()).is_empty() {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// A value from raw bits, with any bits not defined by a variant unset.
+    fn from_bits_truncate(bits: Self::Repr) -> Self {
+        Self::from_bits_retain(bits).intersection(Self::all())
+    }
+
+    /// The name and value of each variant which is set on this value.
+    fn iter_set(self) -> impl Iterator<Item = (&'static str, Self)> {
+        Self::variant_pairs()
+            .iter()
+            .copied()
+            .filter(move |(_, value)| self.contains(*value))
+    }
+
+    /// Whether no bits are set.
+    fn is_empty(self) -> bool {
+        self == Self::empty()
+    }
+
+    /// Whether every named variant is set.
+    fn is_all(self) -> bool {
+        self.contains(Self::all())
+    }
+
+    /// Whether every bit set in `other` is set in this value.
+    fn contains(self, other: Self) -> bool {
+        self & other == other
+    }
+
+    /// Whether any bit set in `other` is set in this value.
+    fn intersects(self, other: Self) -> bool {
+        !(self & other).is_empty()
+    }
+
+    /// The bits set in either value.
+    fn union(self, other: Self) -> Self {
+        self | other
+    }
+
+    /// The bits set in both values.
+    fn intersection(self, other: Self) -> Self {
+        self & other
+    }
+
+    /// The bits set in this value but not in `other`.
+    fn difference(self, other: Self) -> Self {
+        self & !other
+    }
+
+    /// The bits set in exactly one of the values.
+    fn symmetric_difference(self, other: Self) -> Self {
+        self.union(other).difference(self.intersection(other))
+    }
+
+    /// The named variants which aren't set in this value.
+    fn complement(self) -> Self {
+        !self & Self::all()
+    }
+
+    /// Sets the bits set in `other`.
+    fn insert(&mut self, other: Self) {
+        *self |= other;
+    }
+
+    /// Unsets the bits set in `other`.
+    fn remove(&mut self, other: Self) {
+        *self &= !other;
+    }
+
+    /// Flips the bits set in `other`.
+    fn toggle(&mut self, other: Self) {
+        *self = self.symmetric_difference(other);
+    }
+
+    /// Sets or unsets the bits set in `other`.
+    fn set(&mut self, other: Self, value: bool) {
+        if value {
+            self.insert(other);
+        } else {
+            self.remove(other);
+        }
+    }
+}
+
+/// Describes a single variant of a type generated by `#[bitfield_enum]`, as
+/// listed in its `FLAGS` table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FlagInfo<T: 'static> {
+    /// The name of the variant, e.g. `"READ"`.
+    pub name: &'static str,
+    /// The value of the variant.
+    pub value: T,
+    /// The index of the bit, if the variant is a single bit.
+    pub bit: Option<u32>,
+    /// Whether the variant has more than one bit set.
+    pub composite: bool,
+    /// Whether the variant is marked `#[deprecated]`.
+    pub deprecated: bool,
+    /// Whether the variant is marked `#[mask]`.
+    pub mask: bool,
+    /// Whether the variant is marked `#[alias]`.
+    pub alias: bool,
+    /// The text of the variant's doc comment, one line per `///` line.
+    pub doc: &'static str,
+}
+
+/// The error when a string isn't the name of any variant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownNameError {
+    /// The string which didn't match.
+    pub name: String,
+}
+
+impl core::fmt::Display for UnknownNameError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "no variant is named {:?}", self.name)
+    }
+}
+
+impl std::error::Error for UnknownNameError {}
@@ -0,0 +1,2998 @@
+use core::ops::Deref;
+
+use derive_deref::Deref;
+use heck::{ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse::Parse, spanned::Spanned, BinOp, Expr, ExprLit, Ident, ItemEnum, Lit, LitInt, LitStr,
+    Token, Type, Variant,
+};
+
+#[derive(Deref)]
+pub(crate) struct ReprType(Type);
+
+impl Default for ReprType {
+    fn default() -> Self {
+        Self(Type::Verbatim(quote! { u64 }))
+    }
+}
+
+impl Parse for ReprType {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input.is_empty() {
+            Ok(Self::default())
+        } else {
+            input.parse::<Token![as]>()?;
+            let repr_type = Self(input.parse()?);
+            if let Type::Array(array) = &*repr_type {
+                let word_bits = integer_bits(&array.elem.to_token_stream().to_string());
+                if !array.elem.to_token_stream().to_string().starts_with('u') || word_bits.is_none()
+                {
+                    return Err(syn::Error::new(
+                        array.elem.span(),
+                        "array reprs should be arrays of u8, u16, u32, u64 or u128",
+                    ));
+                }
+                match &array.len {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(len),
+                        ..
+                    }) => {
+                        len.base10_parse::<usize>()?;
+                    }
+                    len => {
+                        return Err(syn::Error::new(
+                            len.span(),
+                            "the length of an array repr should be an integer literal",
+                        ))
+                    }
+                }
+            }
+            Ok(repr_type)
+        }
+    }
+}
+
+impl ReprType {
+    /// The word type and number of words of an array repr like `[u64; 4]`.
+    pub(crate) fn array(&self) -> Option<(&Type, usize)> {
+        match &self.0 {
+            Type::Array(syn::TypeArray {
+                elem,
+                len:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Int(len),
+                        ..
+                    }),
+                ..
+            }) => Some((elem, len.base10_parse().expect("validated when parsing"))),
+            _ => None,
+        }
+    }
+
+    /// The number of bits available for variants.
+    fn bits(&self) -> Option<usize> {
+        match self.array() {
+            Some((word, len)) => {
+                integer_bits(&word.to_token_stream().to_string()).map(|bits| bits * len)
+            }
+            None => integer_bits(&self.to_token_stream().to_string()),
+        }
+    }
+
+    /// A value with no bits set.
+    pub(crate) fn zero(&self) -> TokenStream {
+        match self.array() {
+            Some((_, len)) => quote! { [0; #len] },
+            None => quote! { 0 },
+        }
+    }
+
+    /// `lhs & rhs`, word by word for array reprs, in a form which can be used
+    /// in a const fn.
+    pub(crate) fn bitand(&self, lhs: impl ToTokens, rhs: impl ToTokens) -> TokenStream {
+        self.binary_op(lhs, quote! { & }, quote! { &= }, rhs)
+    }
+
+    /// `lhs | rhs`, word by word for array reprs, in a form which can be used
+    /// in a const fn.
+    pub(crate) fn bitor(&self, lhs: impl ToTokens, rhs: impl ToTokens) -> TokenStream {
+        self.binary_op(lhs, quote! { | }, quote! { |= }, rhs)
+    }
+
+    fn binary_op(
+        &self,
+        lhs: impl ToTokens,
+        op: TokenStream,
+        assign_op: TokenStream,
+        rhs: impl ToTokens,
+    ) -> TokenStream {
+        match self.array() {
+            Some((_, len)) => quote! {
+                {
+                    let (mut out, rhs) = (#lhs, #rhs);
+                    let mut i = 0;
+                    while i < #len {
+                        out[i] #assign_op rhs[i];
+                        i += 1;
+                    }
+                    out
+                }
+            },
+            None => quote! { #lhs #op #rhs },
+        }
+    }
+
+    /// The number of bits set in `value`, in a form which can be used in a
+    /// const fn.
+    pub(crate) fn count_ones(&self, value: impl ToTokens) -> TokenStream {
+        match self.array() {
+            Some((_, len)) => quote! {
+                {
+                    let (value, mut out, mut i) = (#value, 0, 0);
+                    while i < #len {
+                        out += value[i].count_ones();
+                        i += 1;
+                    }
+                    out
+                }
+            },
+            None => quote! { #value.count_ones() },
+        }
+    }
+
+    /// The index of the lowest bit set in `value`, in a form which can be used
+    /// in a const fn.
+    pub(crate) fn trailing_zeros(&self, value: impl ToTokens) -> TokenStream {
+        match self.array() {
+            Some((_, len)) => quote! {
+                {
+                    let (value, mut out, mut i) = (#value, 0, 0);
+                    while i < #len {
+                        out += value[i].trailing_zeros();
+                        if value[i] != 0 {
+                            break;
+                        }
+                        i += 1;
+                    }
+                    out
+                }
+            },
+            None => quote! { #value.trailing_zeros() },
+        }
+    }
+
+    /// `!value`, word by word for array reprs, in a form which can be used in
+    /// a const fn.
+    pub(crate) fn not(&self, value: impl ToTokens) -> TokenStream {
+        match self.array() {
+            Some((_, len)) => quote! {
+                {
+                    let mut out = #value;
+                    let mut i = 0;
+                    while i < #len {
+                        out[i] = !out[i];
+                        i += 1;
+                    }
+                    out
+                }
+            },
+            None => quote! { !#value },
+        }
+    }
+}
+
+/// The number of bits in one of the standard integer types.
+fn integer_bits(name: &str) -> Option<usize> {
+    match name {
+        "usize" | "isize" => Some(usize::BITS as usize),
+        name if name.starts_with('u') || name.starts_with('i') => name[1..]
+            .parse()
+            .ok()
+            .filter(|bits| [8, 16, 32, 64, 128].contains(bits)),
+        _ => None,
+    }
+}
+
+/// The contents of the `#[bitfield_enum(...)]` attribute: an optional
+/// `as T`, followed by any number of comma-separated options.
+pub(crate) struct Args {
+    pub(crate) repr_type: ReprType,
+    pub(crate) options: Options,
+}
+
+/// Options which change the generated code.
+pub(crate) struct Options {
+    /// The separator used by the `pipe_separated` serde representation.
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) separator: String,
+    /// The representation used for `Serialize` and `Deserialize` impls on
+    /// the type itself, as a path within the generated serde module.
+    pub(crate) serde: Option<syn::Path>,
+    /// The name of the generated module of serde representations, instead
+    /// of `<type>_serde`.
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) serde_module: Option<Ident>,
+    /// Whether to generate an `Atomic<type>` companion type.
+    pub(crate) atomic: bool,
+    /// Whether to leave out the `PartialOrd` and `Ord` impls, which compare
+    /// the numeric value.
+    pub(crate) no_ord: bool,
+    /// Whether to generate a `c_header()` function.
+    pub(crate) c_header: bool,
+    /// Whether to generate a `<type>Flag` enum of the single-bit variants.
+    pub(crate) flag_enum: bool,
+    /// The prefix of the method checking for each variant, unless the variant
+    /// has a `#[method = "..."]` attribute.
+    pub(crate) method_prefix: String,
+    /// The parts of the generated code left out with `skip(...)`, from
+    /// [`SKIPPABLE`].
+    pub(crate) skip: Vec<String>,
+    /// Whether the names representations serialize the fewest variants which
+    /// make up the value, rather than every variant which is set.
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) minimal_names: bool,
+    /// Whether to leave out everything which lets the type mix with its repr
+    /// type, see [`STRICT_SKIPS`].
+    pub(crate) strict: bool,
+    /// The number of bits given with `bits = N`, which limits the number of
+    /// variants even though the repr type is rounded up to whole words.
+    pub(crate) bits: Option<usize>,
+}
+
+impl Options {
+    /// Whether the part of the generated code named `name` (one of
+    /// [`SKIPPABLE`]) was left out with `skip(...)` or `strict`.
+    pub(crate) fn skips(&self, name: &str) -> bool {
+        self.skip.iter().any(|skipped| skipped == name)
+            || (self.strict && STRICT_SKIPS.contains(&name))
+    }
+}
+
+/// The parts of the generated code which may be left out with `skip(...)`.
+/// The operator impls required by the `BitfieldEnum` trait can't be skipped,
+/// but `Debug` can, as long as the caller writes their own impl.
+const SKIPPABLE: &[&str] = &[
+    "Debug",
+    "Deref",
+    "DerefMut",
+    "From",
+    "repr_ops",
+    "has_methods",
+    "setter_methods",
+    "with_and_without",
+    "subset_comparisons",
+];
+
+/// The parts of the generated code left out with the `strict` option, so that
+/// the raw value is only available through `bits()` and `from_bits*()`.
+const STRICT_SKIPS: &[&str] = &["Deref", "DerefMut", "From", "repr_ops"];
+
+/// The representations which may be chosen with the `serde` option.
+const SERDE_REPRESENTATIONS: &[&str] = &[
+    "numeric_representation",
+    "numeric_representation::stringified",
+    "numeric_representation::strict",
+    "numeric_representation::truncating",
+    "names",
+    "pipe_separated",
+];
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            separator: " | ".to_string(),
+            serde: None,
+            serde_module: None,
+            atomic: false,
+            no_ord: false,
+            c_header: false,
+            flag_enum: false,
+            method_prefix: "has_".to_string(),
+            skip: vec![],
+            strict: false,
+            minimal_names: false,
+            bits: None,
+        }
+    }
+}
+
+impl Parse for Args {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut first = true;
+        let repr_type = if input.peek(Token![as]) {
+            first = false;
+            input.parse()?
+        } else {
+            ReprType::default()
+        };
+        let explicit_repr_type = !first;
+        let mut repr_type = repr_type;
+        let mut options = Options::default();
+        while !input.is_empty() {
+            if !first {
+                input.parse::<Token![,]>()?;
+                if input.is_empty() {
+                    break;
+                }
+            }
+            first = false;
+            let key: Ident = input.parse()?;
+            match key.to_string().as_str() {
+                "separator" => {
+                    input.parse::<Token![=]>()?;
+                    options.separator = input.parse::<LitStr>()?.value();
+                }
+                "serde" => {
+                    input.parse::<Token![=]>()?;
+                    let representation: LitStr = input.parse()?;
+                    if !SERDE_REPRESENTATIONS.contains(&representation.value().as_str()) {
+                        return Err(syn::Error::new(
+                            representation.span(),
+                            format!(
+                                "serde representation should be one of {SERDE_REPRESENTATIONS:?}"
+                            ),
+                        ));
+                    }
+                    options.serde = Some(representation.parse()?);
+                }
+                "serde_module" => {
+                    input.parse::<Token![=]>()?;
+                    options.serde_module = Some(input.parse::<LitStr>()?.parse()?);
+                }
+                "atomic" => options.atomic = true,
+                "no_ord" => options.no_ord = true,
+                "c_header" => options.c_header = true,
+                "flag_enum" => options.flag_enum = true,
+                "strict" => options.strict = true,
+                "minimal_names" => options.minimal_names = true,
+                "method_prefix" => {
+                    input.parse::<Token![=]>()?;
+                    let prefix: LitStr = input.parse()?;
+                    if syn::parse_str::<Ident>(&format!("{}x", prefix.value())).is_err() {
+                        return Err(syn::Error::new(
+                            prefix.span(),
+                            "`method_prefix` should be the start of an identifier",
+                        ));
+                    }
+                    options.method_prefix = prefix.value();
+                }
+                "skip" => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    for name in content.parse_terminated::<Ident, Token![,]>(Ident::parse)? {
+                        if !SKIPPABLE.contains(&name.to_string().as_str()) {
+                            return Err(syn::Error::new(
+                                name.span(),
+                                format!("`{name}` can't be skipped; expected one of {SKIPPABLE:?}"),
+                            ));
+                        }
+                        options.skip.push(name.to_string());
+                    }
+                }
+                "bits" => {
+                    input.parse::<Token![=]>()?;
+                    let bits: LitInt = input.parse()?;
+                    if explicit_repr_type {
+                        return Err(syn::Error::new(
+                            key.span(),
+                            "the `bits` option can't be combined with `as T`",
+                        ));
+                    }
+                    let count = match bits.base10_parse::<usize>()? {
+                        0 => {
+                            return Err(syn::Error::new(bits.span(), "`bits` should be at least 1"))
+                        }
+                        count => count,
+                    };
+                    options.bits = Some(count);
+                    let words = LitInt::new(&count.div_ceil(64).to_string(), bits.span());
+                    repr_type = ReprType(syn::parse_quote! { [u64; #words] });
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("unknown bitfield_enum option `{key}`"),
+                    ))
+                }
+            }
+        }
+        Ok(Self { repr_type, options })
+    }
+}
+
+pub(crate) struct BitfieldEnumCtx {
+    pub(crate) enum_def: ItemEnum,
+    pub(crate) repr_type: ReprType,
+    pub(crate) options: Options,
+}
+
+impl Deref for BitfieldEnumCtx {
+    type Target = ItemEnum;
+    fn deref(&self) -> &Self::Target {
+        &self.enum_def
+    }
+}
+
+/// The identifiers generated for a single variant.
+pub(crate) struct VariantIdents<'a> {
+    pub(crate) variant: &'a Variant,
+    /// The associated constant, e.g. `READ_WRITE` for `ReadWrite`. This is
+    /// also the name used by `Debug`, serde, etc.
+    pub(crate) constant: Ident,
+    /// The method checking whether the variant is set, e.g. `has_read_write`,
+    /// or the name given with `#[method = "..."]`.
+    pub(crate) has_method: Ident,
+    /// The setter methods `set_read_write`, `with_read_write`,
+    /// `without_read_write` and `toggle_read_write`, which are always named
+    /// after the variant.
+    pub(crate) setters: [Ident; 4],
+    /// The variant of the `<type>Flag` enum, e.g. `ReadWrite`.
+    pub(crate) flag_variant: Ident,
+    /// Whether the variant is marked `#[mask]`.
+    pub(crate) mask: bool,
+    /// Whether the variant is marked `#[alias]`.
+    pub(crate) alias: bool,
+}
+
+impl VariantIdents<'_> {
+    /// Whether the variant is a real flag, rather than a `#[mask]` or an
+    /// `#[alias]`. Only these are iterated over, included in `all_set()`,
+    /// written by `Debug` and serialization, and described by `FLAGS`.
+    pub(crate) fn listed(&self) -> bool {
+        !self.mask && !self.alias
+    }
+}
+
+/// The value given by a `#[repr(...)]` attribute on a variant.
+fn repr_override(variant: &Variant) -> Option<TokenStream> {
+    variant
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("repr"))
+        .and_then(|attr| attr.parse_args().ok())
+}
+
+/// The number of bits a variant sets, where it can be worked out before type
+/// checking: one if it has no `#[repr(...)]`, otherwise the repr has to be
+/// integer literals combined with `<<`, `|`, `&` and `^`, or an array of them.
+fn literal_count_ones(variant: &Variant) -> Option<u32> {
+    fn value(expr: &Expr) -> Option<u128> {
+        match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(lit), ..
+            }) => lit.base10_parse().ok(),
+            Expr::Paren(inner) => value(&inner.expr),
+            Expr::Group(inner) => value(&inner.expr),
+            Expr::Binary(binary) => {
+                let (lhs, rhs) = (value(&binary.left)?, value(&binary.right)?);
+                match binary.op {
+                    BinOp::Shl(_) => lhs.checked_shl(rhs.try_into().ok()?),
+                    BinOp::BitOr(_) => Some(lhs | rhs),
+                    BinOp::BitAnd(_) => Some(lhs & rhs),
+                    BinOp::BitXor(_) => Some(lhs ^ rhs),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    let Some(repr) = repr_override(variant) else {
+        return Some(1);
+    };
+    match syn::parse2(repr).ok()? {
+        Expr::Array(array) => array
+            .elems
+            .iter()
+            .map(|word| value(word).map(u128::count_ones))
+            .sum(),
+        expr => value(&expr).map(u128::count_ones),
+    }
+}
+
+/// The method name given by a `#[method = "can_read"]` attribute on a variant.
+fn method_override(variant: &Variant) -> syn::Result<Option<Ident>> {
+    let Some(attr) = variant
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("method"))
+    else {
+        return Ok(None);
+    };
+    match attr.parse_meta()? {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            lit: syn::Lit::Str(name),
+            ..
+        }) => name.parse().map(Some),
+        _ => Err(syn::Error::new(
+            attr.span(),
+            "expected a method name like `#[method = \"can_read\"]`",
+        )),
+    }
+}
+
+impl BitfieldEnumCtx {
+    /// The identifiers generated for each variant. Every code path naming a
+    /// variant's constant or method should go through this.
+    pub(crate) fn variant_idents(&self) -> Vec<VariantIdents<'_>> {
+        self.variants
+            .iter()
+            .map(|variant| {
+                let name = variant.ident.to_string();
+                let span = variant.ident.span();
+                let prefix = &self.options.method_prefix;
+                let snake = name.to_snake_case();
+                let has_method = method_override(variant)
+                    .ok()
+                    .flatten()
+                    .unwrap_or_else(|| Ident::new(&format!("{prefix}{snake}"), span));
+                VariantIdents {
+                    variant,
+                    constant: Ident::new(&name.to_shouty_snake_case(), span),
+                    has_method,
+                    setters: ["set", "with", "without", "toggle"]
+                        .map(|verb| Ident::new(&format!("{verb}_{snake}"), span)),
+                    flag_variant: Ident::new(&name.to_upper_camel_case(), span),
+                    mask: variant.attrs.iter().any(|attr| attr.path.is_ident("mask")),
+                    alias: variant.attrs.iter().any(|attr| attr.path.is_ident("alias")),
+                }
+            })
+            .collect()
+    }
+
+    /// An error for each invalid `#[method]` attribute, and for each variant
+    /// whose generated identifiers collide with those of an earlier one, e.g.
+    /// `ReadWrite` and `READ_WRITE`.
+    pub(crate) fn check_identifiers(&self) -> syn::Result<()> {
+        let mut errors = vec![];
+        // Keyed by whether the identifier is a variant of the `<type>Flag`
+        // enum, rather than an item of the type. The items generated whatever
+        // the variants are have no variant to name.
+        let mut seen: std::collections::HashMap<_, Option<&Ident>> = self
+            .fixed_items()
+            .into_iter()
+            .map(|name| ((false, name), None))
+            .collect();
+        for idents in self.variant_idents() {
+            if let Err(error) = method_override(idents.variant) {
+                errors.push(error);
+                continue;
+            }
+            let marker = ["mask", "alias"].into_iter().find_map(|marker| {
+                let attr = idents
+                    .variant
+                    .attrs
+                    .iter()
+                    .find(|attr| attr.path.is_ident(marker));
+                attr.map(|attr| (marker, attr))
+            });
+            if let Some((marker, attr)) = marker {
+                if repr_override(idents.variant).is_none() {
+                    errors.push(syn::Error::new(
+                        attr.span(),
+                        format!("`#[{marker}]` should have a `#[repr(...)]` giving its bits"),
+                    ));
+                    continue;
+                }
+            }
+            if idents.constant == "FLAGS" || idents.constant == "ALL_FLAGS" {
+                errors.push(syn::Error::new(
+                    idents.variant.ident.span(),
+                    format!(
+                        "`{}` is reserved for the table describing each variant",
+                        idents.constant
+                    ),
+                ));
+                continue;
+            }
+            let items = [&idents.constant, &idents.has_method]
+                .into_iter()
+                .chain(&idents.setters)
+                .map(|ident| (false, ident));
+            let flags = (self.options.flag_enum && literal_count_ones(idents.variant) == Some(1))
+                .then_some((true, &idents.flag_variant));
+            for (is_flag, ident) in items.chain(flags) {
+                let key = (is_flag, ident.to_string());
+                if let Some(previous) = seen.insert(key, Some(&idents.variant.ident)) {
+                    let message = match previous {
+                        Some(previous) => format!(
+                            "`{}` and `{previous}` would both generate `{ident}`",
+                            idents.variant.ident
+                        ),
+                        None => format!(
+                            "`{}` would generate `{ident}`, which clashes with a generated method",
+                            idents.variant.ident
+                        ),
+                    };
+                    errors.push(syn::Error::new(idents.variant.ident.span(), message));
+                    break;
+                }
+            }
+        }
+        match errors.into_iter().reduce(|mut errors, error| {
+            errors.combine(error);
+            errors
+        }) {
+            Some(errors) => Err(errors),
+            None => Ok(()),
+        }
+    }
+
+    /// The inherent items generated on the type whatever its variants are
+    /// named, with the options it was given.
+    fn fixed_items(&self) -> Vec<String> {
+        let mut items: Vec<String> = [
+            "all_set",
+            "all_variant_pairs",
+            "variant_names",
+            "variant_values",
+            "variant_pairs",
+            "names_of_set_variants",
+            "empty",
+            "bits",
+            "from_bits",
+            "from_bits_truncate",
+            "from_bits_retain",
+            "from_name",
+            "from_name_ignore_case",
+            "name",
+            "decompose",
+            "__count_ones",
+            "__bit_index",
+            "__format_bits",
+            "__parse_bits",
+        ]
+        .map(String::from)
+        .into();
+        for endian in ["le", "be"] {
+            items.extend([
+                format!("to_{endian}_bytes"),
+                format!("from_{endian}_bytes"),
+                format!("from_{endian}_bytes_checked"),
+                format!("from_{endian}_bytes_truncate"),
+                format!("read_from_{endian}"),
+                format!("write_to_{endian}"),
+            ]);
+        }
+        let optional: &[(bool, &[&str])] = &[
+            (
+                !self.options.skips("with_and_without"),
+                &["with", "without"],
+            ),
+            (
+                !self.options.skips("subset_comparisons"),
+                &[
+                    "is_subset_of",
+                    "is_superset_of",
+                    "is_disjoint",
+                    "subset_cmp",
+                ],
+            ),
+            (self.options.flag_enum, &["flags"]),
+            (self.options.c_header, &["c_header"]),
+            (cfg!(feature = "clap"), &["clap_arg", "from_clap_matches"]),
+        ];
+        for (generated, names) in optional {
+            if *generated {
+                items.extend(names.iter().map(|name| name.to_string()));
+            }
+        }
+        items
+    }
+
+    /// An expression checking whether the variant is set on `self`, which may
+    /// be a reference. This calls the `has_` method unless it was skipped.
+    fn is_set(&self, idents: &VariantIdents) -> TokenStream {
+        let VariantIdents {
+            constant,
+            has_method,
+            ..
+        } = idents;
+        if self.options.skips("has_methods") {
+            let type_name = &self.ident;
+            quote! { (*self & #type_name::#constant) == #type_name::#constant }
+        } else {
+            quote! { self.#has_method() }
+        }
+    }
+
+    pub(crate) fn constant_values(&self) -> Vec<impl ToTokens> {
+        let repr_type = &self.repr_type;
+        let available = match self.options.bits {
+            Some(bits) => Some((bits, format!("`bits = {bits}`"))),
+            None => repr_type
+                .bits()
+                .map(|bits| (bits, format!("`{}`", repr_type.to_token_stream()))),
+        };
+        self.variant_idents()
+            .into_iter()
+            .enumerate()
+            .map(
+                |(
+                    i,
+                    VariantIdents {
+                        variant,
+                        constant: name,
+                        ..
+                    },
+                )| {
+                    let repr = repr_override(variant).unwrap_or_else(|| match &available {
+                        Some((bits, repr)) if i >= *bits => syn::Error::new(
+                            variant.span(),
+                            format!("{repr} only has room for {bits} variants"),
+                        )
+                        .to_compile_error(),
+                        _ => match repr_type.array() {
+                            Some((word, len)) => {
+                                let word_bits = integer_bits(&word.to_token_stream().to_string())
+                                    .expect("validated when parsing");
+                                let words = (0..len).map(|w| {
+                                    let n = if w == i / word_bits {
+                                        1u128 << (i % word_bits)
+                                    } else {
+                                        0
+                                    };
+                                    LitInt::new(
+                                        &format!("{n}{}", word.to_token_stream()),
+                                        name.span(),
+                                    )
+                                });
+                                quote! { [#(#words),*] }
+                            }
+                            None => {
+                                let n = LitInt::new(
+                                    &format!("{}{}", 1u128 << i, repr_type.to_token_stream()),
+                                    name.span(),
+                                );
+                                quote! { #n }
+                            }
+                        },
+                    });
+                    let type_name = &self.ident;
+                    let vis = &self.vis;
+                    let docs = variant
+                        .attrs
+                        .iter()
+                        .filter(|attr| attr.path.is_ident("doc"));
+                    quote! {
+                        #(#docs)*
+                        #vis const #name: #type_name = #type_name(#repr);
+                    }
+                },
+            )
+            .collect()
+    }
+
+    /// A method like `has_x` for each variant like `X`.
+    pub(crate) fn has_methods(&self) -> Vec<impl ToTokens> {
+        let vis = &self.vis;
+        self.variant_idents()
+            .into_iter()
+            .map(|idents| {
+                let VariantIdents {
+                    constant: variant_name,
+                    has_method: fn_name,
+                    ..
+                } = idents;
+                quote! {
+                    #[doc=concat!("Check if this bitfield has the ", stringify!(#variant_name), " flag set.")]
+                    #vis fn #fn_name(self) -> bool {
+                        (self & Self::#variant_name) == Self::#variant_name
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Methods like `set_x`, `with_x`, `without_x` and `toggle_x` for each
+    /// variant like `X`.
+    pub(crate) fn setter_methods(&self) -> Vec<impl ToTokens> {
+        let vis = &self.vis;
+        self.variant_idents()
+            .into_iter()
+            .map(|idents| {
+                let VariantIdents {
+                    constant: variant_name,
+                    setters: [set, with, without, toggle],
+                    ..
+                } = idents;
+                quote! {
+                    #[doc=concat!("Set or unset the ", stringify!(#variant_name), " flag.")]
+                    #vis fn #set(&mut self, value: bool) {
+                        if value {
+                            *self |= Self::#variant_name;
+                        } else {
+                            *self &= !Self::#variant_name;
+                        }
+                    }
+
+                    #[doc=concat!("This value with the ", stringify!(#variant_name), " flag set.")]
+                    #vis fn #with(self) -> Self {
+                        self | Self::#variant_name
+                    }
+
+                    #[doc=concat!("This value with the ", stringify!(#variant_name), " flag unset.")]
+                    #vis fn #without(self) -> Self {
+                        self & !Self::#variant_name
+                    }
+
+                    #[doc=concat!("Flip the ", stringify!(#variant_name), " flag.")]
+                    #vis fn #toggle(&mut self) {
+                        *self = (*self & !Self::#variant_name) | (!*self & Self::#variant_name);
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Adds `.with()` and `.without()` methods
+    pub(crate) fn with_and_without(&self) -> impl ToTokens {
+        quote! {
+            /// Combines this flag with `other`.
+            fn with(self, other: impl Into<Self>) -> Self
+            {
+                self | other.into()
+            }
+
+            /// Returns this value with `other` ensured to be unset.
+            fn without(self, other: impl Into<Self>) -> Self
+            {
+                self & (!(other.into()))
+            }
+        }
+    }
+
+    /// Explicit conversions to and from the raw bits, and to and from bytes
+    /// in either byte order.
+    pub(crate) fn bits_and_bytes(&self) -> impl ToTokens {
+        let vis = &self.vis;
+        let repr_type = &*self.repr_type;
+        let size = quote! { core::mem::size_of::<#repr_type>() };
+        let endian = ["le", "be"].map(|endian| {
+            let to_bytes = format_ident!("to_{}_bytes", endian);
+            let from_bytes = format_ident!("from_{}_bytes", endian);
+            let from_bytes_checked = format_ident!("from_{}_bytes_checked", endian);
+            let from_bytes_truncate = format_ident!("from_{}_bytes_truncate", endian);
+            let read_from = format_ident!("read_from_{}", endian);
+            let write_to = format_ident!("write_to_{}", endian);
+            let order = if endian == "le" {
+                "little-endian"
+            } else {
+                "big-endian"
+            };
+            let (to_bytes_body, from_bytes_body) = match self.repr_type.array() {
+                // The first word is the least significant, so big-endian
+                // bytes start with the last one.
+                Some((word, len)) => {
+                    let offset = if endian == "le" {
+                        quote! { i * word_size }
+                    } else {
+                        quote! { (#len - 1 - i) * word_size }
+                    };
+                    (
+                        quote! {
+                            let word_size = core::mem::size_of::<#word>();
+                            let mut out = [0u8; #size];
+                            let mut i = 0;
+                            while i < #len {
+                                let word = self.0[i].#to_bytes();
+                                let mut j = 0;
+                                while j < word_size {
+                                    out[#offset + j] = word[j];
+                                    j += 1;
+                                }
+                                i += 1;
+                            }
+                            out
+                        },
+                        quote! {
+                            let word_size = core::mem::size_of::<#word>();
+                            let mut out: #repr_type = [0; #len];
+                            let mut i = 0;
+                            while i < #len {
+                                let mut word = [0u8; core::mem::size_of::<#word>()];
+                                let mut j = 0;
+                                while j < word_size {
+                                    word[j] = bytes[#offset + j];
+                                    j += 1;
+                                }
+                                out[i] = #word::#from_bytes(word);
+                                i += 1;
+                            }
+                            Self(out)
+                        },
+                    )
+                }
+                None => (
+                    quote! { self.0.#to_bytes() },
+                    quote! { Self(#repr_type::#from_bytes(bytes)) },
+                ),
+            };
+            quote! {
+                #[doc=concat!("The raw bits as ", #order, " bytes.")]
+                #vis const fn #to_bytes(self) -> [u8; #size] {
+                    #to_bytes_body
+                }
+
+                #[doc=concat!("A value from ", #order, " bytes, keeping any bits not defined by a variant.")]
+                #vis const fn #from_bytes(bytes: [u8; #size]) -> Self {
+                    #from_bytes_body
+                }
+
+                #[doc=concat!("A value from ", #order, " bytes, or `None` if any bits not defined by a variant are set.")]
+                #vis fn #from_bytes_checked(bytes: [u8; #size]) -> Option<Self> {
+                    Self::from_bits(Self::#from_bytes(bytes).0)
+                }
+
+                #[doc=concat!("A value from ", #order, " bytes, with any bits not defined by a variant unset.")]
+                #vis fn #from_bytes_truncate(bytes: [u8; #size]) -> Self {
+                    Self::from_bits_truncate(Self::#from_bytes(bytes).0)
+                }
+
+                #[doc=concat!("Reads a value as ", #order, " bytes, keeping any bits not defined by a variant.")]
+                #vis fn #read_from(mut reader: impl std::io::Read) -> std::io::Result<Self> {
+                    let mut bytes = [0u8; #size];
+                    reader.read_exact(&mut bytes)?;
+                    Ok(Self::#from_bytes(bytes))
+                }
+
+                #[doc=concat!("Writes the raw bits as ", #order, " bytes.")]
+                #vis fn #write_to(self, mut writer: impl std::io::Write) -> std::io::Result<()> {
+                    writer.write_all(&self.#to_bytes())
+                }
+            }
+        });
+        // Integers are written in decimal; array reprs as a single
+        // hexadecimal number, since there's no wider integer to parse them as.
+        let (format_bits, parse_bits) = match self.repr_type.array() {
+            Some((word, len)) => (
+                quote! {
+                    let digits = core::mem::size_of::<#word>() * 2;
+                    let mut out = String::from("0x");
+                    for word in bits.iter().rev() {
+                        if out.len() > 2 {
+                            out.push_str(&format!("{:0digits$x}", word));
+                        } else if *word != 0 {
+                            out.push_str(&format!("{:x}", word));
+                        }
+                    }
+                    if out.len() == 2 {
+                        out.push('0');
+                    }
+                    out
+                },
+                quote! {
+                    let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X"))?;
+                    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+                        return None;
+                    }
+                    let digits = digits.trim_start_matches('0');
+                    let word_digits = core::mem::size_of::<#word>() * 2;
+                    if digits.len() > word_digits * #len {
+                        return None;
+                    }
+                    let mut out: #repr_type = [0; #len];
+                    let mut end = digits.len();
+                    for word in out.iter_mut() {
+                        if end == 0 {
+                            break;
+                        }
+                        let start = end.saturating_sub(word_digits);
+                        *word = #word::from_str_radix(&digits[start..end], 16).ok()?;
+                        end = start;
+                    }
+                    Some(out)
+                },
+            ),
+            None => (quote! { bits.to_string() }, quote! { text.parse().ok() }),
+        };
+        quote! {
+            /// The raw bits of this value.
+            #vis const fn bits(self) -> #repr_type {
+                self.0
+            }
+
+            /// A value from raw bits, or `None` if any bits not defined by a
+            /// variant are set.
+            #vis fn from_bits(bits: #repr_type) -> Option<Self> {
+                if Self(bits) & !Self::all_set() == Self::empty() {
+                    Some(Self(bits))
+                } else {
+                    None
+                }
+            }
+
+            /// A value from raw bits, with any bits not defined by a variant
+            /// unset.
+            #vis fn from_bits_truncate(bits: #repr_type) -> Self {
+                Self(bits) & Self::all_set()
+            }
+
+            /// A value from raw bits, keeping any bits not defined by a
+            /// variant.
+            #vis const fn from_bits_retain(bits: #repr_type) -> Self {
+                Self(bits)
+            }
+
+            #(#endian)*
+
+            #[allow(dead_code)]
+            fn __format_bits(bits: #repr_type) -> String {
+                #format_bits
+            }
+
+            #[allow(dead_code)]
+            fn __parse_bits(text: &str) -> Option<#repr_type> {
+                #parse_bits
+            }
+        }
+    }
+
+    /// An `Atomic<type>` wrapping the atomic integer of the same size, if the
+    /// `atomic` option was given.
+    pub(crate) fn atomic_type(&self) -> impl ToTokens {
+        if !self.options.atomic {
+            return quote! {};
+        }
+        let vis = &self.vis;
+        let type_name = &self.ident;
+        let repr_type = &*self.repr_type;
+        let atomic_name = format_ident!("Atomic{}", type_name);
+        let atomic_repr = match repr_type.to_token_stream().to_string().as_str() {
+            "u8" => quote! { core::sync::atomic::AtomicU8 },
+            "u16" => quote! { core::sync::atomic::AtomicU16 },
+            "u32" => quote! { core::sync::atomic::AtomicU32 },
+            "u64" => quote! { core::sync::atomic::AtomicU64 },
+            "usize" => quote! { core::sync::atomic::AtomicUsize },
+            "i8" => quote! { core::sync::atomic::AtomicI8 },
+            "i16" => quote! { core::sync::atomic::AtomicI16 },
+            "i32" => quote! { core::sync::atomic::AtomicI32 },
+            "i64" => quote! { core::sync::atomic::AtomicI64 },
+            "isize" => quote! { core::sync::atomic::AtomicIsize },
+            other => {
+                let message =
+                    format!("the `atomic` option isn't supported for `{other}`, which has no atomic equivalent in core::sync::atomic");
+                return syn::Error::new(repr_type.span(), message).to_compile_error();
+            }
+        };
+        quote! {
+            #[doc=concat!("A `", stringify!(#type_name), "` which can be shared between threads.")]
+            #[repr(transparent)]
+            #[derive(Default)]
+            #vis struct #atomic_name(#atomic_repr);
+
+            impl #atomic_name {
+                #[doc=concat!("A new `", stringify!(#atomic_name), "` holding `value`.")]
+                #vis const fn new(value: #type_name) -> Self {
+                    Self(<#atomic_repr>::new(value.0))
+                }
+
+                /// Consumes the atomic and returns the contained value.
+                #vis fn into_inner(self) -> #type_name {
+                    #type_name(self.0.into_inner())
+                }
+
+                /// Loads the value.
+                #vis fn load(&self, order: core::sync::atomic::Ordering) -> #type_name {
+                    #type_name(self.0.load(order))
+                }
+
+                /// Stores `value`.
+                #vis fn store(&self, value: #type_name, order: core::sync::atomic::Ordering) {
+                    self.0.store(value.0, order)
+                }
+
+                /// Stores `value`, returning the previous value.
+                #vis fn swap(&self, value: #type_name, order: core::sync::atomic::Ordering) -> #type_name {
+                    #type_name(self.0.swap(value.0, order))
+                }
+
+                /// Bitwise-or with `value`, returning the previous value.
+                #vis fn fetch_or(&self, value: #type_name, order: core::sync::atomic::Ordering) -> #type_name {
+                    #type_name(self.0.fetch_or(value.0, order))
+                }
+
+                /// Bitwise-and with `value`, returning the previous value.
+                #vis fn fetch_and(&self, value: #type_name, order: core::sync::atomic::Ordering) -> #type_name {
+                    #type_name(self.0.fetch_and(value.0, order))
+                }
+
+                /// Bitwise-xor with `value`, returning the previous value.
+                #vis fn fetch_xor(&self, value: #type_name, order: core::sync::atomic::Ordering) -> #type_name {
+                    #type_name(self.0.fetch_xor(value.0, order))
+                }
+
+                /// Sets the flags in `value`, returning the previous value.
+                #vis fn fetch_insert(&self, value: #type_name, order: core::sync::atomic::Ordering) -> #type_name {
+                    self.fetch_or(value, order)
+                }
+
+                /// Unsets the flags in `value`, returning the previous value.
+                #vis fn fetch_remove(&self, value: #type_name, order: core::sync::atomic::Ordering) -> #type_name {
+                    self.fetch_and(!value, order)
+                }
+
+                /// Stores `new` if the value is `current`. Returns the previous
+                /// value, as `Ok` if it was `current`.
+                #vis fn compare_exchange(
+                    &self,
+                    current: #type_name,
+                    new: #type_name,
+                    success: core::sync::atomic::Ordering,
+                    failure: core::sync::atomic::Ordering,
+                ) -> Result<#type_name, #type_name> {
+                    self.0
+                        .compare_exchange(current.0, new.0, success, failure)
+                        .map(#type_name)
+                        .map_err(#type_name)
+                }
+
+                /// Stores the result of `f` until it succeeds, unless it returns
+                /// `None`. Returns the previous value, as `Ok` if `f` returned
+                /// `Some`.
+                #vis fn fetch_update<F>(
+                    &self,
+                    set_order: core::sync::atomic::Ordering,
+                    fetch_order: core::sync::atomic::Ordering,
+                    mut f: F,
+                ) -> Result<#type_name, #type_name>
+                where
+                    F: FnMut(#type_name) -> Option<#type_name>,
+                {
+                    self.0
+                        .fetch_update(set_order, fetch_order, |value| f(#type_name(value)).map(|value| value.0))
+                        .map(#type_name)
+                        .map_err(#type_name)
+                }
+            }
+
+            impl From<#type_name> for #atomic_name {
+                fn from(value: #type_name) -> Self {
+                    Self::new(value)
+                }
+            }
+
+            impl std::fmt::Debug for #atomic_name {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    std::fmt::Debug::fmt(&self.load(core::sync::atomic::Ordering::Relaxed), f)
+                }
+            }
+        }
+    }
+
+    /// A `c_header()` function returning a C header which defines the type
+    /// and each of its variants, if the `c_header` option was given.
+    pub(crate) fn c_header(&self) -> impl ToTokens {
+        if !self.options.c_header {
+            return quote! {};
+        }
+        let vis = &self.vis;
+        let type_name = &self.ident;
+        let repr_type = &*self.repr_type;
+        let c_type = match repr_type.to_token_stream().to_string().as_str() {
+            "u8" => "uint8_t",
+            "u16" => "uint16_t",
+            "u32" => "uint32_t",
+            "u64" => "uint64_t",
+            "u128" => "unsigned __int128",
+            "usize" => "uintptr_t",
+            "i8" => "int8_t",
+            "i16" => "int16_t",
+            "i32" => "int32_t",
+            "i64" => "int64_t",
+            "i128" => "__int128",
+            "isize" => "intptr_t",
+            other => {
+                let message = format!("the `c_header` option isn't supported for `{other}`");
+                return syn::Error::new(repr_type.span(), message).to_compile_error();
+            }
+        };
+        let prefix = type_name.to_string().to_shouty_snake_case();
+        let guard = format!("{prefix}_H");
+        let typedef = format!("typedef {c_type} {type_name};\n");
+        let (name, value): (Vec<_>, Vec<_>) = self
+            .variant_idents()
+            .into_iter()
+            .map(|idents| (format!("{prefix}_{}", idents.constant), idents.constant))
+            .unzip();
+        // C has no integer constants wider than 64 bits, so wider values are
+        // built from two halves
+        let wide = c_type.ends_with("__int128");
+        let define = name.iter().zip(&value).map(|(name, value)| {
+            if wide {
+                quote! {
+                    let bits = Self::#value.0 as u128;
+                    if bits >> 64 == 0 {
+                        writeln!(header, "#define {} (({}){:#x})", #name, stringify!(#type_name), bits)
+                    } else {
+                        writeln!(
+                            header,
+                            "#define {} ((({}){:#x}ULL << 64) | {:#x}ULL)",
+                            #name,
+                            stringify!(#type_name),
+                            bits >> 64,
+                            bits as u64,
+                        )
+                    }
+                    .expect("writing to a String can't fail");
+                }
+            } else {
+                quote! {
+                    writeln!(header, "#define {} (({}){:#x})", #name, stringify!(#type_name), Self::#value.0)
+                        .expect("writing to a String can't fail");
+                }
+            }
+        });
+        quote! {
+            #[doc=concat!("A C header defining `", stringify!(#type_name), "` and a constant for each variant.")]
+            #vis fn c_header() -> String {
+                use std::fmt::Write;
+
+                let mut header = String::new();
+                header.push_str(concat!(
+                    "/* Generated from the ", stringify!(#type_name), " bitfield_enum. */\n",
+                    "#ifndef ", #guard, "\n",
+                    "#define ", #guard, "\n\n",
+                    "#include <stdint.h>\n\n",
+                    #typedef, "\n",
+                ));
+                #(#define)*
+                header.push_str(concat!("\n#endif /* ", #guard, " */\n"));
+                header
+            }
+        }
+    }
+
+    /// A fieldless `<type>Flag` enum with a variant for each single-bit
+    /// variant, for exhaustive matching, along with conversions to and from
+    /// the type and a `flags()` iterator over those which are set. A variant
+    /// whose `#[repr]` is too complex to count the bits of here is checked at
+    /// compile time instead, so that a single bit can't be silently left out.
+    pub(crate) fn flag_enum(&self) -> impl ToTokens {
+        let vis = &self.vis;
+        let type_name = &self.ident;
+        let flag_name = format_ident!("{}Flag", type_name);
+        let listed: Vec<_> = self
+            .variant_idents()
+            .into_iter()
+            .filter(|idents| idents.listed())
+            .map(|idents| (literal_count_ones(idents.variant), idents))
+            .collect();
+        let single_bits: Vec<_> = listed
+            .iter()
+            .filter(|(count, _)| *count == Some(1))
+            .map(|(_, idents)| idents)
+            .collect();
+        let unknown = listed.iter().filter(|(count, _)| count.is_none());
+        let not_single_bit = unknown.map(|(_, idents)| {
+            let constant = &idents.constant;
+            quote! {
+                const _: () = assert!(
+                    #type_name::__count_ones(#type_name::#constant.0) != 1,
+                    concat!(
+                        "`", stringify!(#constant), "` is a single bit, but its #[repr] isn't ",
+                        "made of integer literals, so it can't be a variant of `",
+                        stringify!(#flag_name), "`; write the repr like `1 << 3`, ",
+                        "or leave out the flag_enum option"
+                    ),
+                );
+            }
+        });
+        let flag: Vec<_> = single_bits
+            .iter()
+            .map(|idents| &idents.flag_variant)
+            .collect();
+        let constant: Vec<_> = single_bits.iter().map(|idents| &idents.constant).collect();
+        let docs = single_bits.iter().map(|idents| {
+            let docs = idents
+                .variant
+                .attrs
+                .iter()
+                .filter(|attr| attr.path.is_ident("doc"));
+            quote! { #(#docs)* }
+        });
+        quote! {
+            #[doc=concat!("A single flag of [`", stringify!(#type_name), "`], for exhaustive matching.")]
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+            #vis enum #flag_name {
+                #(
+                    #docs
+                    #flag,
+                )*
+            }
+
+            impl #flag_name {
+                /// Every flag, in the order they were declared.
+                #vis const ALL: &'static [Self] = &[#(Self::#flag),*];
+            }
+
+            impl From<#flag_name> for #type_name {
+                fn from(flag: #flag_name) -> Self {
+                    match flag {
+                        #(#flag_name::#flag => Self::#constant,)*
+                    }
+                }
+            }
+
+            impl TryFrom<#type_name> for #flag_name {
+                /// The value, which isn't exactly one flag.
+                type Error = #type_name;
+
+                fn try_from(value: #type_name) -> Result<Self, Self::Error> {
+                    #(
+                        if value == #type_name::#constant {
+                            return Ok(Self::#flag);
+                        }
+                    )*
+                    Err(value)
+                }
+            }
+
+            #(#not_single_bit)*
+
+            impl #type_name {
+                #[doc=concat!("Each flag which is set on this value, as a [`", stringify!(#flag_name), "`].")]
+                #vis fn flags(self) -> impl Iterator<Item = #flag_name> {
+                    #flag_name::ALL
+                        .iter()
+                        .copied()
+                        .filter(move |flag| {
+                            let flag = #type_name::from(*flag);
+                            self & flag == flag
+                        })
+                }
+            }
+        }
+    }
+
+    /// Lookups between variants and their names, with a generated `match`
+    /// rather than a search through `variant_pairs()`.
+    pub(crate) fn name_lookups(&self) -> impl ToTokens {
+        let vis = &self.vis;
+        let (name, constant) = self.all_name_value_pairs();
+        quote! {
+            /// The variant with exactly this name, if any.
+            #vis fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    #(#name => Some(Self::#constant),)*
+                    _ => None,
+                }
+            }
+
+            /// The variant with this name, ignoring case, if any.
+            #vis fn from_name_ignore_case(name: &str) -> Option<Self> {
+                Self::from_name(&name.to_uppercase())
+            }
+
+            /// The name of the variant which is exactly this value, if any.
+            #[allow(unreachable_patterns)] // variants may share a value
+            #vis fn name(self) -> Option<&'static str> {
+                match self {
+                    #(Self::#constant => Some(#name),)*
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// `decompose()`, splitting a value into the fewest variants which make it
+    /// up. Greedily taking the widest variant isn't enough once composites
+    /// overlap, so this searches every cover exactly. Each step branches on the
+    /// variants containing some still-uncovered group of bits which no variant
+    /// splits, and drops any branch which can't beat the best cover so far.
+    pub(crate) fn decompose(&self) -> impl ToTokens {
+        let vis = &self.vis;
+        let type_name = &self.ident;
+        let repr_type = &*self.repr_type;
+        quote! {
+            /// The fewest variants whose union is this value, in the order they
+            /// were declared, along with any bits which aren't part of any of
+            /// them.
+            #vis fn decompose(self) -> (impl Iterator<Item = Self>, #repr_type) {
+                fn cover(
+                    candidates: &[(usize, #type_name)],
+                    remaining: #type_name,
+                    parts: &mut Vec<usize>,
+                    best: &mut Option<Vec<usize>>,
+                ) {
+                    let bound = best.as_ref().map(Vec::len);
+                    if remaining == #type_name::empty() {
+                        if bound.map_or(true, |bound| parts.len() < bound) {
+                            *best = Some(parts.clone());
+                        }
+                        return;
+                    }
+                    if bound.is_some_and(|bound| parts.len() + 1 >= bound) {
+                        return;
+                    }
+                    // every cover holds a variant containing all of `atom`
+                    let mut atom = remaining;
+                    for &(_, candidate) in candidates {
+                        if atom & candidate != #type_name::empty() {
+                            atom &= candidate;
+                        }
+                    }
+                    for &(i, candidate) in candidates {
+                        if atom & candidate != #type_name::empty() {
+                            parts.push(i);
+                            cover(candidates, remaining & !candidate, parts, best);
+                            parts.pop();
+                        }
+                    }
+                }
+
+                let variants = Self::variant_values();
+                let mut candidates: Vec<(usize, Self)> = vec![];
+                let mut covered = Self::empty();
+                for (i, variant) in variants.iter().enumerate() {
+                    let duplicate = candidates.iter().any(|(_, seen)| seen == variant);
+                    if self & *variant == *variant && !duplicate {
+                        candidates.push((i, *variant));
+                        covered |= *variant;
+                    }
+                }
+                let mut best = None;
+                cover(&candidates, covered, &mut vec![], &mut best);
+                let mut parts = best.unwrap_or_default();
+                parts.sort_unstable();
+                (
+                    parts.into_iter().map(move |i| variants[i]),
+                    (self & !covered).0,
+                )
+            }
+        }
+    }
+
+    /// `TryFrom<&str>`, for the variant with exactly that name.
+    pub(crate) fn impl_try_from_str(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        quote! {
+            impl TryFrom<&str> for #type_name {
+                type Error = ::classic_bitfield::UnknownNameError;
+
+                fn try_from(name: &str) -> Result<Self, Self::Error> {
+                    Self::from_name(name).ok_or_else(|| ::classic_bitfield::UnknownNameError {
+                        name: name.to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    /// A `FLAGS` table describing each variant. Whether a variant is a single
+    /// bit is worked out from its value at compile time, since its `#[repr]`
+    /// may be any constant expression.
+    pub(crate) fn flags_table(&self) -> impl ToTokens {
+        let vis = &self.vis;
+        let count_ones = self.repr_type.count_ones(quote! { bits });
+        let trailing_zeros = self.repr_type.trailing_zeros(quote! { bits });
+        let idents = self.variant_idents();
+        let flag_info = |idents: &VariantIdents| {
+            let constant = &idents.constant;
+            let (mask, alias) = (idents.mask, idents.alias);
+            let name = constant.to_string();
+            let deprecated = idents
+                .variant
+                .attrs
+                .iter()
+                .any(|attr| attr.path.is_ident("deprecated"));
+            let doc = doc_comment(&idents.variant.attrs);
+            quote! {
+                ::classic_bitfield::FlagInfo {
+                    name: #name,
+                    value: Self::#constant,
+                    bit: Self::__bit_index(Self::#constant.0),
+                    composite: Self::__count_ones(Self::#constant.0) > 1,
+                    deprecated: #deprecated,
+                    mask: #mask,
+                    alias: #alias,
+                    doc: #doc,
+                }
+            }
+        };
+        let flag = idents
+            .iter()
+            .filter(|idents| idents.listed())
+            .map(flag_info);
+        let all_flag = idents.iter().map(flag_info);
+        let repr_type = &*self.repr_type;
+        quote! {
+            /// A description of each variant, including its doc comment.
+            #vis const FLAGS: &'static [::classic_bitfield::FlagInfo<Self>] = &[#(#flag),*];
+
+            /// A description of each variant including `#[mask]` and `#[alias]`
+            /// variants.
+            #vis const ALL_FLAGS: &'static [::classic_bitfield::FlagInfo<Self>] = &[#(#all_flag),*];
+
+            const fn __count_ones(bits: #repr_type) -> u32 {
+                #count_ones
+            }
+
+            const fn __bit_index(bits: #repr_type) -> Option<u32> {
+                if Self::__count_ones(bits) == 1 {
+                    Some(#trailing_zeros)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// The `BitfieldEnum` trait from the runtime crate, for generic code.
+    pub(crate) fn impl_bitfield_enum_trait(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        let repr_type = &*self.repr_type;
+        quote! {
+            impl ::classic_bitfield::BitfieldEnum for #type_name {
+                type Repr = #repr_type;
+
+                fn variant_pairs() -> &'static [(&'static str, Self)] {
+                    #type_name::variant_pairs()
+                }
+
+                fn bits(self) -> #repr_type {
+                    self.0
+                }
+
+                fn from_bits_retain(bits: #repr_type) -> Self {
+                    Self(bits)
+                }
+
+                fn empty() -> Self {
+                    #type_name::empty()
+                }
+
+                fn all() -> Self {
+                    #type_name::all_set()
+                }
+            }
+        }
+    }
+
+    /// Impls for From<repr_type>/Into<type_name> and vice-versa.
+    pub(crate) fn impl_from(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        let repr_type = &*self.repr_type;
+        quote! {
+            impl From<#repr_type> for #type_name {
+                fn from(value: #repr_type) -> Self {
+                    Self(value)
+                }
+            }
+
+            impl From<#type_name> for #repr_type {
+                fn from(value: #type_name) -> Self {
+                    value.0
+                }
+            }
+        }
+    }
+
+    /// Deref of the internal value.
+    pub(crate) fn impl_deref(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        let repr_type = &*self.repr_type;
+        quote! {
+            impl core::ops::Deref for #type_name {
+                type Target = #repr_type;
+                fn deref(&self) -> &Self::Target {
+                    &self.0
+                }
+            }
+        }
+    }
+
+    /// DerefMut of the internal value.
+    pub(crate) fn impl_deref_mut(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        quote! {
+            impl core::ops::DerefMut for #type_name {
+                fn deref_mut(&mut self) -> &mut Self::Target {
+                    &mut self.0
+                }
+            }
+        }
+    }
+
+    /// Impls for bitwise-and operations
+    pub(crate) fn impl_bitand(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        let with_self = self.repr_type.bitand(quote! { self.0 }, quote! { rhs.0 });
+        quote! {
+            impl core::ops::BitAnd<#type_name> for #type_name {
+                type Output = #type_name;
+                fn bitand(self, rhs: #type_name) -> Self::Output {
+                    Self(#with_self)
+                }
+            }
+
+            impl core::ops::BitAndAssign for #type_name {
+                fn bitand_assign(&mut self, rhs: Self) {
+                    *self = *self & rhs;
+                }
+            }
+        }
+    }
+
+    /// Impls for bitwise-or operations
+    pub(crate) fn impl_bitor(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        let with_self = self.repr_type.bitor(quote! { self.0 }, quote! { rhs.0 });
+        quote! {
+            impl core::ops::BitOr<#type_name> for #type_name {
+                type Output = #type_name;
+                fn bitor(self, rhs: #type_name) -> Self::Output {
+                    Self(#with_self)
+                }
+            }
+
+            impl core::ops::BitOrAssign for #type_name {
+                fn bitor_assign(&mut self, rhs: Self) {
+                    *self = *self | rhs;
+                }
+            }
+
+            impl core::iter::FromIterator<#type_name> for #type_name {
+                fn from_iter<I: IntoIterator<Item = #type_name>>(iter: I) -> Self {
+                    iter.into_iter().fold(Self::empty(), |acc, value| acc | value)
+                }
+            }
+
+            impl core::iter::Extend<#type_name> for #type_name {
+                fn extend<I: IntoIterator<Item = #type_name>>(&mut self, iter: I) {
+                    for value in iter {
+                        *self |= value;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Bitwise operations and comparisons between the type and its repr
+    /// type, in either order.
+    pub(crate) fn impl_repr_ops(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        let repr_type = &*self.repr_type;
+        let and_repr = self.repr_type.bitand(quote! { self.0 }, quote! { rhs });
+        let repr_and = self.repr_type.bitand(quote! { self }, quote! { rhs.0 });
+        let or_repr = self.repr_type.bitor(quote! { self.0 }, quote! { rhs });
+        let repr_or = self.repr_type.bitor(quote! { self }, quote! { rhs.0 });
+        let compare = match self.repr_type.array() {
+            Some(_) => quote! { Some(self.cmp(&Self(*other))) },
+            None => quote! { self.0.partial_cmp(other) },
+        };
+        let impl_partial_ord = (!self.options.no_ord).then(|| {
+            quote! {
+                impl core::cmp::PartialOrd<#repr_type> for #type_name {
+                    fn partial_cmp(&self, other: &#repr_type) -> Option<std::cmp::Ordering> {
+                        #compare
+                    }
+                }
+            }
+        });
+        quote! {
+            impl core::ops::BitAnd<#repr_type> for #type_name {
+                type Output = #type_name;
+                fn bitand(self, rhs: #repr_type) -> Self::Output {
+                    Self(#and_repr)
+                }
+            }
+
+            impl core::ops::BitAnd<#type_name> for #repr_type {
+                type Output = #type_name;
+                fn bitand(self, rhs: #type_name) -> Self::Output {
+                    #type_name(#repr_and)
+                }
+            }
+
+            impl core::ops::BitAndAssign<#repr_type> for #type_name {
+                fn bitand_assign(&mut self, rhs: #repr_type) {
+                    *self = *self & rhs;
+                }
+            }
+
+            impl core::ops::BitOr<#type_name> for #repr_type {
+                type Output = #type_name;
+                fn bitor(self, rhs: #type_name) -> Self::Output {
+                    #type_name(#repr_or)
+                }
+            }
+
+            impl core::ops::BitOr<#repr_type> for #type_name {
+                type Output = #type_name;
+                fn bitor(self, rhs: #repr_type) -> Self::Output {
+                    Self(#or_repr)
+                }
+            }
+
+            impl core::ops::BitOrAssign<#repr_type> for #type_name {
+                fn bitor_assign(&mut self, rhs: #repr_type) {
+                    *self = *self | rhs;
+                }
+            }
+
+            impl core::cmp::PartialEq<#repr_type> for #type_name {
+                fn eq(&self, other: &#repr_type) -> bool {
+                    self.0 == *other
+                }
+            }
+
+            #impl_partial_ord
+        }
+    }
+
+    pub(crate) fn impl_not(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        let not = self.repr_type.not(quote! { self.0 });
+        quote! {
+            impl core::ops::Not for #type_name {
+                type Output = Self;
+
+                fn not(self) -> Self::Output {
+                    Self(#not)
+                }
+            }
+        }
+    }
+
+    /// The derived traits. The numeric ordering is left out with the
+    /// `no_ord` option, and implemented by hand for array reprs.
+    pub(crate) fn derives(&self) -> impl ToTokens {
+        if self.options.no_ord || self.repr_type.array().is_some() {
+            quote! { #[derive(Clone, Copy, PartialEq, Eq)] }
+        } else {
+            quote! { #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)] }
+        }
+    }
+
+    /// The numeric ordering of array reprs, which would otherwise compare
+    /// their least significant word first.
+    pub(crate) fn impl_ord(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        if self.options.no_ord || self.repr_type.array().is_none() {
+            return quote! {};
+        }
+        quote! {
+            impl core::cmp::PartialOrd for #type_name {
+                fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                    Some(self.cmp(other))
+                }
+            }
+
+            impl core::cmp::Ord for #type_name {
+                fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                    self.0.iter().rev().cmp(other.0.iter().rev())
+                }
+            }
+        }
+    }
+
+    /// Comparisons of which flags are set, as opposed to the numeric ordering.
+    pub(crate) fn subset_comparisons(&self) -> impl ToTokens {
+        let vis = &self.vis;
+        quote! {
+            /// Whether every flag set on this value is also set on `other`.
+            #vis fn is_subset_of(self, other: Self) -> bool {
+                self & other == self
+            }
+
+            /// Whether every flag set on `other` is also set on this value.
+            #vis fn is_superset_of(self, other: Self) -> bool {
+                self & other == other
+            }
+
+            /// Whether no flag is set on both this value and `other`.
+            #vis fn is_disjoint(self, other: Self) -> bool {
+                self & other == Self::empty()
+            }
+
+            /// Compares the flags which are set: `Less` for a strict subset of
+            /// `other`, `Greater` for a strict superset, and `None` if neither
+            /// contains the other.
+            #vis fn subset_cmp(self, other: Self) -> Option<core::cmp::Ordering> {
+                match (self.is_subset_of(other), self.is_superset_of(other)) {
+                    (true, true) => Some(core::cmp::Ordering::Equal),
+                    (true, false) => Some(core::cmp::Ordering::Less),
+                    (false, true) => Some(core::cmp::Ordering::Greater),
+                    (false, false) => None,
+                }
+            }
+        }
+    }
+
+    pub(crate) fn impl_debug(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        let bits = match self.repr_type.array() {
+            Some(_) => quote! { Self::__format_bits(self.0) },
+            None => quote! { self.0 },
+        };
+        let check_each_variant = self.variant_idents().into_iter().filter(VariantIdents::listed).map(|idents| {
+            let variant_name = &idents.constant;
+            let is_set = self.is_set(&idents);
+            quote! {
+                if #is_set {
+                    if at_least_one {
+                        write!(f, " | ")?;
+                    }
+                    at_least_one = true;
+                    write!(f, concat!(stringify!(#type_name), "::", stringify!(#variant_name)))?;
+                }
+            }
+        });
+        quote! {
+            impl std::fmt::Debug for #type_name {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    let mut at_least_one = false;
+
+                    #(#check_each_variant)*
+
+                    if !at_least_one {
+                        write!(f, concat!(stringify!(#type_name), "({})"), #bits)?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// The name and constant of each variant which isn't a `#[mask]` or an
+    /// `#[alias]`.
+    fn name_value_pairs(&self) -> (Vec<String>, Vec<Ident>) {
+        self.variant_idents()
+            .into_iter()
+            .filter(VariantIdents::listed)
+            .map(|idents| (idents.constant.to_string(), idents.constant))
+            .unzip()
+    }
+
+    /// The name and constant of every variant.
+    fn all_name_value_pairs(&self) -> (Vec<String>, Vec<Ident>) {
+        self.variant_idents()
+            .into_iter()
+            .map(|idents| (idents.constant.to_string(), idents.constant))
+            .unzip()
+    }
+
+    pub(crate) fn impl_iter_variants(&self) -> impl ToTokens {
+        let (key, value) = self.name_value_pairs();
+        let (all_key, all_value) = self.all_name_value_pairs();
+        let type_name = &self.ident;
+        let vis = &self.vis;
+        let zero = self.repr_type.zero();
+        quote! {
+            /// The name of each variant
+            #vis const fn variant_names() -> &'static [&'static str] {
+                &[
+                    #(#key),*
+                ]
+            }
+
+            /// Each value defined by a name
+            #vis const fn variant_values() -> &'static [Self] {
+                &[
+                    #(Self::#value),*
+                ]
+            }
+
+            /// The name of each variant along with the corresponding value.
+            #vis const fn variant_pairs() -> &'static [(&'static str, Self)] {
+                &[
+                    #(
+                        (#key, Self::#value)
+                    ),*
+                ]
+            }
+
+            /// The name and value of each variant, including `#[mask]` and
+            /// `#[alias]` variants.
+            #vis const fn all_variant_pairs() -> &'static [(&'static str, Self)] {
+                &[
+                    #(
+                        (#all_key, Self::#all_value)
+                    ),*
+                ]
+            }
+
+            #[doc=concat!("An instance of `", stringify!(#type_name), "` with no variants set")]
+            #vis const fn empty() -> Self {
+                Self(#zero)
+            }
+
+            #[doc=concat!("An instance of `", stringify!(#type_name), "` with all named variants set on")]
+            #vis fn all_set() -> Self {
+                Self::empty() | #( Self::#value )|*
+            }
+
+            #[doc=concat!("The names of each variant which is set on this instance of `", stringify!(#type_name), "`")]
+            #vis fn names_of_set_variants(self) -> Vec<&'static str> {
+                let mut names = vec![];
+                #(
+                    if (self & Self::#value) == Self::#value {
+                        names.push(#key);
+                    }
+                )*
+                names
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn impl_serde(&self) -> impl ToTokens {
+        let vis = &self.vis;
+        let type_name = &self.ident;
+        let repr_type = &*self.repr_type;
+        let numeric = self.serde_numeric();
+        let (describe_undefined, pipe_separated_numbers) = match self.repr_type.array() {
+            Some(_) => (
+                quote! {
+                    format!(
+                        "bits {} (undefined bits {})",
+                        Self::__format_bits(value.0),
+                        Self::__format_bits(undefined.0),
+                    )
+                },
+                quote! {},
+            ),
+            None => (
+                quote! { format!("integer {} (undefined bits {:#b})", value.0, undefined.0) },
+                quote! {
+                    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        match #repr_type::try_from(v) {
+                            Ok(v) => Ok(#type_name(v)),
+                            Err(_) => Err(serde::de::Error::invalid_value(Unexpected::Unsigned(v), &self)),
+                        }
+                    }
+
+                    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        match #repr_type::try_from(v) {
+                            Ok(v) => Ok(#type_name(v)),
+                            Err(_) => Err(serde::de::Error::invalid_value(Unexpected::Signed(v), &self)),
+                        }
+                    }
+                },
+            ),
+        };
+        let (variant, is_set): &(Vec<_>, Vec<_>) = &self
+            .variant_idents()
+            .into_iter()
+            .filter(VariantIdents::listed)
+            .map(|idents| (idents.constant.to_string(), self.is_set(&idents)))
+            .unzip();
+        let (serialize_each_name, names_and_unnamed) = if self.options.minimal_names {
+            (
+                quote! {
+                    for name in self.decompose().0.filter_map(Self::name) {
+                        seq.serialize_element(name)?;
+                    }
+                },
+                quote! {
+                    let (parts, unnamed) = self.decompose();
+                    let mut out = parts.filter_map(Self::name).collect::<Vec<_>>().join(SEPARATOR);
+                    let unnamed = Self(unnamed);
+                },
+            )
+        } else {
+            (
+                quote! {
+                    #(
+                        if #is_set {
+                            seq.serialize_element(#variant)?;
+                        }
+                    )*
+                },
+                quote! {
+                    let mut out = self.names_of_set_variants().join(SEPARATOR);
+                    let unnamed = *self & !Self::all_set();
+                },
+            )
+        };
+        let separator = &self.options.separator;
+        let mod_name = self.options.serde_module.clone().unwrap_or_else(|| {
+            Ident::new(
+                &format!("{}_serde", type_name.to_string().to_snake_case()),
+                type_name.span(),
+            )
+        });
+        let serde_impls = self.options.serde.as_ref().map(|representation| {
+            quote! {
+                impl serde::Serialize for #type_name {
+                    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                    where
+                        S: serde::Serializer,
+                    {
+                        #mod_name::#representation::serialize(self, serializer)
+                    }
+                }
+
+                impl<'de> serde::Deserialize<'de> for #type_name {
+                    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                    where
+                        D: serde::Deserializer<'de>,
+                    {
+                        #mod_name::#representation::deserialize(deserializer)
+                    }
+                }
+            }
+        });
+        quote! {
+            #vis mod #mod_name {
+                /// Implemented by the bitfield type so that the functions in
+                /// these modules can refer to it from wherever it's declared.
+                #[doc(hidden)]
+                pub trait Representations: Sized {
+                    fn serialize_numeric<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+                    fn deserialize_numeric<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+                    fn serialize_stringified<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+                    fn deserialize_strict<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+                    fn deserialize_truncating<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+                    fn serialize_names<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+                    fn deserialize_names<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+                    fn serialize_pipe_separated<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+                    fn deserialize_pipe_separated<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+                }
+
+                pub mod numeric_representation {
+                    use super::Representations;
+
+                    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+                    where
+                        T: Representations,
+                        S: serde::Serializer,
+                    {
+                        value.serialize_numeric(serializer)
+                    }
+
+                    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+                    where
+                        T: Representations,
+                        D: serde::Deserializer<'de>,
+                    {
+                        T::deserialize_numeric(deserializer)
+                    }
+
+                    pub mod stringified {
+                        use super::Representations;
+
+                        pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+                        where
+                            T: Representations,
+                            S: serde::Serializer,
+                        {
+                            value.serialize_stringified(serializer)
+                        }
+
+                        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+                        where
+                            T: Representations,
+                            D: serde::Deserializer<'de>,
+                        {
+                            T::deserialize_numeric(deserializer)
+                        }
+                    }
+
+                    /// Like the numeric representation, but fails to
+                    /// deserialize values with bits set which aren't defined
+                    /// by any variant.
+                    pub mod strict {
+                        use super::Representations;
+
+                        pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+                        where
+                            T: Representations,
+                            S: serde::Serializer,
+                        {
+                            value.serialize_numeric(serializer)
+                        }
+
+                        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+                        where
+                            T: Representations,
+                            D: serde::Deserializer<'de>,
+                        {
+                            T::deserialize_strict(deserializer)
+                        }
+                    }
+
+                    /// Like the numeric representation, but bits which aren't
+                    /// defined by any variant are unset when deserializing.
+                    pub mod truncating {
+                        use super::Representations;
+
+                        pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+                        where
+                            T: Representations,
+                            S: serde::Serializer,
+                        {
+                            value.serialize_numeric(serializer)
+                        }
+
+                        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+                        where
+                            T: Representations,
+                            D: serde::Deserializer<'de>,
+                        {
+                            T::deserialize_truncating(deserializer)
+                        }
+                    }
+                }
+
+                pub mod names {
+                    use super::Representations;
+
+                    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+                    where
+                        T: Representations,
+                        S: serde::Serializer,
+                    {
+                        value.serialize_names(serializer)
+                    }
+
+                    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+                    where
+                        T: Representations,
+                        D: serde::Deserializer<'de>,
+                    {
+                        T::deserialize_names(deserializer)
+                    }
+                }
+
+                pub mod pipe_separated {
+                    use super::Representations;
+
+                    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+                    where
+                        T: Representations,
+                        S: serde::Serializer,
+                    {
+                        value.serialize_pipe_separated(serializer)
+                    }
+
+                    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+                    where
+                        T: Representations,
+                        D: serde::Deserializer<'de>,
+                    {
+                        T::deserialize_pipe_separated(deserializer)
+                    }
+                }
+            }
+
+            const _: () = {
+                use serde::de::Unexpected;
+
+                const SEPARATOR: &str = #separator;
+
+                /// A single variant, deserialized from its name.
+                struct Flag(#type_name);
+
+                impl<'de> serde::Deserialize<'de> for Flag {
+                    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                    where
+                        D: serde::Deserializer<'de>,
+                    {
+                        struct FlagVisitor;
+
+                        impl<'v> serde::de::Visitor<'v> for FlagVisitor {
+                            type Value = Flag;
+                            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                                write!(formatter, "any of these values: {:?}", #type_name::variant_names())
+                            }
+
+                            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+                            where
+                                E: serde::de::Error,
+                            {
+                                match #type_name::from_name(value) {
+                                    Some(value) => Ok(Flag(value)),
+                                    None => Err(serde::de::Error::unknown_variant(
+                                        value,
+                                        #type_name::variant_names(),
+                                    )),
+                                }
+                            }
+
+                            fn visit_borrowed_str<E>(self, value: &'v str) -> Result<Self::Value, E>
+                            where
+                                E: serde::de::Error,
+                            {
+                                self.visit_str(value)
+                            }
+
+                            fn visit_string<E>(self, value: String) -> Result<Self::Value, E>
+                            where
+                                E: serde::de::Error,
+                            {
+                                self.visit_str(&value)
+                            }
+                        }
+
+                        deserializer.deserialize_identifier(FlagVisitor)
+                    }
+                }
+
+                /// Parse a single name (ignoring case) or number.
+                fn parse_flag<E: serde::de::Error>(flag: &str) -> Result<#type_name, E> {
+                    if let Some(value) = #type_name::from_name_ignore_case(flag) {
+                        return Ok(value);
+                    }
+                    match #type_name::__parse_bits(flag) {
+                        Some(value) => Ok(#type_name(value)),
+                        None => Err(serde::de::Error::unknown_variant(
+                            flag,
+                            #type_name::variant_names(),
+                        )),
+                    }
+                }
+
+                impl #mod_name::Representations for #type_name {
+                    #numeric
+
+                    fn serialize_stringified<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                        serializer.serialize_str(&Self::__format_bits(self.0))
+                    }
+
+                    fn deserialize_strict<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                        let value = Self::deserialize_numeric(deserializer)?;
+                        let undefined = value & !Self::all_set();
+                        if undefined == Self::empty() {
+                            Ok(value)
+                        } else {
+                            Err(serde::de::Error::invalid_value(
+                                Unexpected::Other(&#describe_undefined),
+                                &format!(
+                                    "a combination of these values: {:?}",
+                                    Self::variant_names()
+                                )
+                                .as_str(),
+                            ))
+                        }
+                    }
+
+                    fn deserialize_truncating<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                        Self::deserialize_numeric(deserializer).map(|value| value & Self::all_set())
+                    }
+
+                    fn serialize_names<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                        use serde::ser::SerializeSeq;
+
+                        let mut seq = serializer.serialize_seq(None)?;
+                        #serialize_each_name
+                        seq.end()
+                    }
+
+                    fn deserialize_names<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                        struct MyVisitor;
+
+                        impl<'v> serde::de::Visitor<'v> for MyVisitor {
+                            type Value = #type_name;
+                            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                                write!(formatter, "a list of any of these values: {:?}", #type_name::variant_names())
+                            }
+
+                            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+                            where
+                                A: serde::de::SeqAccess<'v>,
+                            {
+                                let mut value = #type_name::empty();
+
+                                while let Some(Flag(member)) = seq.next_element()? {
+                                    value |= member;
+                                }
+
+                                Ok(value)
+                            }
+                        }
+
+                        deserializer.deserialize_seq(MyVisitor)
+                    }
+
+                    fn serialize_pipe_separated<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                        #names_and_unnamed
+                        if unnamed != Self::empty() {
+                            if !out.is_empty() {
+                                out.push_str(SEPARATOR);
+                            }
+                            out.push_str(&Self::__format_bits(unnamed.0));
+                        }
+                        serializer.serialize_str(&out)
+                    }
+
+                    fn deserialize_pipe_separated<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                        struct MyVisitor;
+
+                        impl<'v> serde::de::Visitor<'v> for MyVisitor {
+                            type Value = #type_name;
+                            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                                write!(
+                                    formatter,
+                                    "a string of any of these values separated by {:?}: {:?}",
+                                    SEPARATOR,
+                                    #type_name::variant_names(),
+                                )
+                            }
+
+                            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+                            where
+                                E: serde::de::Error,
+                            {
+                                let separator = match SEPARATOR.trim() {
+                                    "" => SEPARATOR,
+                                    trimmed => trimmed,
+                                };
+                                let mut out = #type_name::empty();
+                                for flag in value.split(separator) {
+                                    let flag = flag.trim();
+                                    if !flag.is_empty() {
+                                        out |= parse_flag(flag)?;
+                                    }
+                                }
+                                Ok(out)
+                            }
+
+                            #pipe_separated_numbers
+                        }
+
+                        // only self-describing formats can hold a plain number
+                        if deserializer.is_human_readable() {
+                            deserializer.deserialize_any(MyVisitor)
+                        } else {
+                            deserializer.deserialize_str(MyVisitor)
+                        }
+                    }
+                }
+            };
+
+            #serde_impls
+        }
+    }
+
+    /// `serialize_numeric` and `deserialize_numeric`. Array reprs are written
+    /// as a sequence of words, least significant first, and can also be read
+    /// from a hexadecimal string.
+    #[cfg(feature = "serde")]
+    fn serde_numeric(&self) -> TokenStream {
+        let type_name = &self.ident;
+        let repr_type = &*self.repr_type;
+        if let Some((_, len)) = self.repr_type.array() {
+            let zero = self.repr_type.zero();
+            return quote! {
+                fn serialize_numeric<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    use serde::ser::SerializeTuple;
+
+                    let mut tuple = serializer.serialize_tuple(#len)?;
+                    for word in &self.0 {
+                        tuple.serialize_element(word)?;
+                    }
+                    tuple.end()
+                }
+
+                fn deserialize_numeric<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    struct MyVisitor;
+
+                    impl<'v> serde::de::Visitor<'v> for MyVisitor {
+                        type Value = #type_name;
+                        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                            write!(
+                                formatter,
+                                "{} integers, least significant first, or a hexadecimal string",
+                                #len
+                            )
+                        }
+
+                        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+                        where
+                            A: serde::de::SeqAccess<'v>,
+                        {
+                            let mut bits: #repr_type = #zero;
+                            for (i, word) in bits.iter_mut().enumerate() {
+                                *word = seq
+                                    .next_element()?
+                                    .ok_or_else(|| serde::de::Error::invalid_length(i, &self))?;
+                            }
+                            if seq.next_element::<serde::de::IgnoredAny>()?.is_some() {
+                                return Err(serde::de::Error::invalid_length(#len + 1, &self));
+                            }
+                            Ok(#type_name(bits))
+                        }
+
+                        fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                            #type_name::__parse_bits(value)
+                                .map(#type_name)
+                                .ok_or_else(|| serde::de::Error::invalid_value(Unexpected::Str(value), &self))
+                        }
+                    }
+
+                    deserializer.deserialize_any(MyVisitor)
+                }
+            };
+        }
+        let serialize_method = Ident::new(
+            &format!("serialize_{}", &self.repr_type.to_token_stream()),
+            syn::__private::Span::call_site(),
+        );
+        let visit_method = Ident::new(
+            &format!("visit_{}", self.repr_type.to_token_stream()),
+            syn::__private::Span::call_site(),
+        );
+        let maybe_convertible_types = self.maybe_convertible_types();
+        let (signed_conversion_visit_method, signed_conversion_type): (Vec<_>, Vec<_>) =
+            maybe_convertible_types
+                .iter()
+                .filter_map(|t| {
+                    let stringified = t.to_token_stream().to_string();
+                    if stringified.starts_with('i') {
+                        Some((format_ident!("visit_{}", stringified), t))
+                    } else {
+                        None
+                    }
+                })
+                .unzip();
+        let (unsigned_conversion_visit_method, unsigned_conversion_type): (Vec<_>, Vec<_>) =
+            maybe_convertible_types
+                .iter()
+                .filter_map(|t| {
+                    let stringified = t.to_token_stream().to_string();
+                    if stringified.starts_with('u') {
+                        Some((format_ident!("visit_{}", stringified), t))
+                    } else {
+                        None
+                    }
+                })
+                .unzip();
+        let bigint_conversion = {
+            let repr_type_string = self.repr_type.to_token_stream().to_string();
+            let mut out = vec![];
+            if repr_type_string != "u128" {
+                out.push(quote! {
+                    fn visit_u128<E>(self, v: u128) -> Result<Self::Value, E>
+                    where
+                        E: serde::de::Error
+                    {
+                        match #repr_type::try_from(v) {
+                            Ok(v) => self.#visit_method(v),
+                            Err(err) => Err(serde::de::Error::invalid_value(Unexpected::Other(&v.to_string()), &self))
+                        }
+                    }
+                });
+            }
+            if repr_type_string != "i128" {
+                out.push(quote! {
+                    fn visit_i128<E>(self, v: i128) -> Result<Self::Value, E>
+                    where
+                        E: serde::de::Error
+                    {
+                        match #repr_type::try_from(v) {
+                            Ok(v) => self.#visit_method(v),
+                            Err(err) => Err(serde::de::Error::invalid_value(Unexpected::Other(&v.to_string()), &self))
+                        }
+                    }
+                })
+            }
+            out
+        };
+        quote! {
+            fn serialize_numeric<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.#serialize_method(self.0)
+            }
+
+            fn deserialize_numeric<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct MyVisitor;
+
+                impl<'v> serde::de::Visitor<'v> for MyVisitor {
+                    type Value = #type_name;
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        write!(formatter, "integer between {} and {}", #repr_type::MIN, #repr_type::MAX)
+                    }
+
+                    fn #visit_method<E>(self, v: #repr_type) -> Result<Self::Value, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        Ok(#type_name(v))
+                    }
+
+                    #(
+                        fn #signed_conversion_visit_method<E>(self, v: #signed_conversion_type) -> Result<Self::Value, E>
+                        where
+                            E: serde::de::Error
+                        {
+                            match #repr_type::try_from(v) {
+                                Ok(v) => self.#visit_method(v),
+                                Err(_) => Err(serde::de::Error::invalid_value(Unexpected::Signed(v.into()), &self))
+                            }
+                        }
+                    )*
+                    #(
+                        fn #unsigned_conversion_visit_method<E>(self, v: #unsigned_conversion_type) -> Result<Self::Value, E>
+                        where
+                            E: serde::de::Error
+                        {
+                            match #repr_type::try_from(v) {
+                                Ok(v) => self.#visit_method(v),
+                                Err(_) => Err(serde::de::Error::invalid_value(Unexpected::Unsigned(v.into()), &self))
+                            }
+                        }
+                    )*
+                    #(#bigint_conversion)*
+
+                    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                        if let Ok(value) = value.parse::<#repr_type>() {
+                            Ok(#type_name(value))
+                        } else {
+                            Err(serde::de::Error::invalid_value(Unexpected::Str(value), &self))
+                        }
+                    }
+                }
+
+                deserializer.deserialize_any(MyVisitor)
+            }
+        }
+    }
+
+    /// All of the integer types except the one representing the output
+    /// bitfield, for trying to convert.
+    #[cfg(feature = "serde")]
+    fn maybe_convertible_types(&self) -> Vec<impl ToTokens> {
+        let mut out = vec![];
+        let repr_type = &self.repr_type;
+        let repr_type_name = repr_type.to_token_stream().to_string();
+        for signedness in ['u', 'i'] {
+            for size in (3..7).map(|i| 1 << i) {
+                let name = format!("{signedness}{size}");
+                if name != repr_type_name {
+                    let ident = Ident::new(&name, repr_type.span());
+                    out.push(ident);
+                }
+            }
+        }
+        out
+    }
+
+    /// A `JsonSchema` impl describing whichever serde representation was
+    /// chosen for the type, or the numeric representation if none was.
+    #[cfg(feature = "schemars")]
+    pub(crate) fn impl_schemars(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        let repr_type = &*self.repr_type;
+        let description = doc_comment(&self.attrs);
+        let representation = self
+            .options
+            .serde
+            .as_ref()
+            .and_then(|path| path.segments.last())
+            .map(|segment| segment.ident.to_string());
+        let schema = match representation.as_deref() {
+            Some("names") => {
+                let variant = self
+                    .variant_idents()
+                    .into_iter()
+                    .filter(VariantIdents::listed)
+                    .map(|idents| {
+                        let name = idents.constant.to_string();
+                        let description = doc_comment(&idents.variant.attrs);
+                        quote! {
+                            {
+                                "const": #name,
+                                "description": #description,
+                            }
+                        }
+                    });
+                quote! {
+                    schemars::json_schema!({
+                        "description": #description,
+                        "type": "array",
+                        "uniqueItems": true,
+                        "items": {
+                            "oneOf": [#(#variant),*],
+                        },
+                    })
+                }
+            }
+            Some("pipe_separated") => {
+                let separator = &self.options.separator;
+                quote! {
+                    schemars::json_schema!({
+                        "description": #description,
+                        "type": "string",
+                        "examples": [#type_name::variant_names().join(#separator)],
+                    })
+                }
+            }
+            Some("stringified") => {
+                let pattern = match self.repr_type.array() {
+                    Some(_) => "^0[xX][0-9a-fA-F]+$",
+                    None => "^-?[0-9]+$",
+                };
+                quote! {
+                    schemars::json_schema!({
+                        "description": #description,
+                        "type": "string",
+                        "pattern": #pattern,
+                    })
+                }
+            }
+            _ => {
+                if let Some((_, len)) = self.repr_type.array() {
+                    quote! {
+                        schemars::json_schema!({
+                            "description": #description,
+                            "type": "array",
+                            "items": {
+                                "type": "integer",
+                                "minimum": 0,
+                            },
+                            "minItems": #len,
+                            "maxItems": #len,
+                        })
+                    }
+                } else {
+                    let repr_type_name = repr_type.to_token_stream().to_string();
+                    if repr_type_name.ends_with("128") {
+                        // serde_json's `Value` can't hold the bounds.
+                        quote! {
+                            schemars::json_schema!({
+                                "description": #description,
+                                "type": "integer",
+                            })
+                        }
+                    } else {
+                        quote! {
+                            schemars::json_schema!({
+                                "description": #description,
+                                "type": "integer",
+                                "minimum": #repr_type::MIN,
+                                "maximum": #repr_type::MAX,
+                            })
+                        }
+                    }
+                }
+            }
+        };
+        quote! {
+            impl schemars::JsonSchema for #type_name {
+                fn schema_name() -> std::borrow::Cow<'static, str> {
+                    stringify!(#type_name).into()
+                }
+
+                fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+                    #schema
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "schemars"))]
+    pub(crate) fn impl_schemars(&self) -> impl ToTokens {
+        quote! {}
+    }
+
+    /// A clap value parser which reads comma-separated variant names,
+    /// ignoring case.
+    #[cfg(feature = "clap")]
+    pub(crate) fn impl_clap(&self) -> impl ToTokens {
+        let vis = &self.vis;
+        let type_name = &self.ident;
+        let parser_name = format_ident!("{}ValueParser", type_name);
+        let (name, help): (Vec<_>, Vec<_>) = self
+            .variant_idents()
+            .into_iter()
+            .filter(VariantIdents::listed)
+            .map(|idents| {
+                (
+                    idents.constant.to_string(),
+                    doc_comment(&idents.variant.attrs),
+                )
+            })
+            .unzip();
+        quote! {
+            #[doc=concat!("Parses `", stringify!(#type_name), "` from a comma-separated list of variant names, or a number.")]
+            #[derive(Clone, Copy, Debug, Default)]
+            #vis struct #parser_name;
+
+            impl clap::builder::TypedValueParser for #parser_name {
+                type Value = #type_name;
+
+                fn parse_ref(
+                    &self,
+                    cmd: &clap::Command,
+                    arg: Option<&clap::Arg>,
+                    value: &std::ffi::OsStr,
+                ) -> Result<Self::Value, clap::Error> {
+                    let invalid = |value: &str| {
+                        let arg = arg
+                            .map(|arg| arg.to_string())
+                            .unwrap_or_else(|| "...".to_string());
+                        clap::Error::raw(
+                            clap::error::ErrorKind::InvalidValue,
+                            format!(
+                                "invalid value '{}' for '{}'\n  [possible values: {}]\n",
+                                value,
+                                arg,
+                                #type_name::variant_names().join(", "),
+                            ),
+                        )
+                        .with_cmd(cmd)
+                    };
+                    let value = value
+                        .to_str()
+                        .ok_or_else(|| clap::Error::new(clap::error::ErrorKind::InvalidUtf8).with_cmd(cmd))?;
+                    let mut out = #type_name::empty();
+                    for flag in value.split(',') {
+                        let flag = flag.trim();
+                        if flag.is_empty() {
+                            continue;
+                        }
+                        out |= #type_name::from_name_ignore_case(flag)
+                            .or_else(|| #type_name::__parse_bits(flag).map(#type_name))
+                            .ok_or_else(|| invalid(flag))?;
+                    }
+                    Ok(out)
+                }
+
+                fn possible_values(
+                    &self,
+                ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+                    Some(Box::new(
+                        [#(clap::builder::PossibleValue::new(#name).help(#help)),*].into_iter(),
+                    ))
+                }
+            }
+
+            impl clap::builder::ValueParserFactory for #type_name {
+                type Parser = #parser_name;
+
+                fn value_parser() -> Self::Parser {
+                    #parser_name
+                }
+            }
+
+            impl #type_name {
+                /// Sets up the argument to parse this type, accepting it any
+                /// number of times, e.g. `--perm read --perm write`. Read it back
+                /// with `from_clap_matches()`.
+                #vis fn clap_arg(arg: clap::Arg) -> clap::Arg {
+                    arg.value_parser(#parser_name).action(clap::ArgAction::Append)
+                }
+
+                /// Every value given for the argument, combined with `|`, or an
+                /// empty value if it wasn't given.
+                #vis fn from_clap_matches(matches: &clap::ArgMatches, id: &str) -> Self {
+                    matches
+                        .get_many::<Self>(id)
+                        .into_iter()
+                        .flatten()
+                        .copied()
+                        .collect()
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "clap"))]
+    pub(crate) fn impl_clap(&self) -> impl ToTokens {
+        quote! {}
+    }
+
+    /// The type is `#[repr(transparent)]`, so it's plain old data whenever
+    /// the repr type is.
+    #[cfg(feature = "bytemuck")]
+    pub(crate) fn impl_bytemuck(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        quote! {
+            unsafe impl bytemuck::Zeroable for #type_name {}
+
+            unsafe impl bytemuck::Pod for #type_name {}
+        }
+    }
+
+    #[cfg(not(feature = "bytemuck"))]
+    pub(crate) fn impl_bytemuck(&self) -> impl ToTokens {
+        quote! {}
+    }
+
+    /// zerocopy's traits can only be implemented by its derive macros, which
+    /// require its "derive" feature.
+    #[cfg(feature = "zerocopy")]
+    pub(crate) fn derive_zerocopy(&self) -> impl ToTokens {
+        quote! {
+            #[derive(
+                zerocopy::FromBytes,
+                zerocopy::IntoBytes,
+                zerocopy::Immutable,
+                zerocopy::KnownLayout,
+            )]
+        }
+    }
+
+    #[cfg(not(feature = "zerocopy"))]
+    pub(crate) fn derive_zerocopy(&self) -> impl ToTokens {
+        quote! {}
+    }
+
+    /// Arbitrary values for fuzzing may have any bits set, including ones
+    /// which aren't defined by a variant.
+    #[cfg(feature = "arbitrary")]
+    pub(crate) fn impl_arbitrary(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        let repr_type = &*self.repr_type;
+        quote! {
+            impl<'a> arbitrary::Arbitrary<'a> for #type_name {
+                fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+                    Ok(Self(u.arbitrary()?))
+                }
+
+                fn size_hint(depth: usize) -> (usize, Option<usize>) {
+                    <#repr_type as arbitrary::Arbitrary<'a>>::size_hint(depth)
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "arbitrary"))]
+    pub(crate) fn impl_arbitrary(&self) -> impl ToTokens {
+        quote! {}
+    }
+
+    /// A proptest strategy producing combinations of variants, which shrinks
+    /// towards fewer variants being set. The parameter chooses whether bits
+    /// not defined by any variant may be set as well.
+    #[cfg(feature = "proptest")]
+    pub(crate) fn impl_proptest(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        let repr_type = &*self.repr_type;
+        quote! {
+            impl proptest::arbitrary::Arbitrary for #type_name {
+                type Parameters = bool;
+                type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+                fn arbitrary_with(include_undefined_bits: Self::Parameters) -> Self::Strategy {
+                    use proptest::strategy::Strategy;
+
+                    let variants = #type_name::variant_values();
+                    let named = proptest::sample::subsequence(variants, 0..=variants.len())
+                        .prop_map(|variants| variants.into_iter().collect::<#type_name>());
+                    if include_undefined_bits {
+                        (named, proptest::arbitrary::any::<#repr_type>())
+                            .prop_map(|(named, bits)| named | (#type_name(bits) & !#type_name::all_set()))
+                            .boxed()
+                    } else {
+                        named.boxed()
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "proptest"))]
+    pub(crate) fn impl_proptest(&self) -> impl ToTokens {
+        quote! {}
+    }
+
+    /// Random combinations of the named variants.
+    #[cfg(feature = "rand")]
+    pub(crate) fn impl_rand(&self) -> impl ToTokens {
+        let type_name = &self.ident;
+        quote! {
+            impl rand::distributions::Distribution<#type_name> for rand::distributions::Standard {
+                fn sample<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> #type_name {
+                    #type_name::variant_values()
+                        .iter()
+                        .filter(|_| rng.gen())
+                        .copied()
+                        .collect()
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "rand"))]
+    pub(crate) fn impl_rand(&self) -> impl ToTokens {
+        quote! {}
+    }
+
+    #[cfg(not(feature = "serde"))]
+    pub(crate) fn impl_serde(&self) -> impl ToTokens {
+        if self.options.serde.is_some() {
+            quote! {
+                compile_error!("the `serde` option requires the \"serde\" feature of classic-bitfield");
+            }
+        } else {
+            quote! {}
+        }
+    }
+}
+
+/// The text of the doc comments in `attrs`, one line per `///` line.
+fn doc_comment(attrs: &[syn::Attribute]) -> String {
+    use syn::{Lit, Meta, MetaNameValue};
+
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(MetaNameValue {
+                lit: Lit::Str(doc), ..
+            })) => Some(doc.value().trim().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The error from checking the identifiers of `item` with `args`, if any.
+    fn check(args: TokenStream, item: TokenStream) -> Option<String> {
+        let Args { repr_type, options } = syn::parse2(args).expect("parse args");
+        let ctx = BitfieldEnumCtx {
+            enum_def: syn::parse2(item).expect("parse enum"),
+            repr_type,
+            options,
+        };
+        ctx.check_identifiers().err().map(|error| error.to_string())
+    }
+
+    #[test]
+    fn masks_and_aliases_need_a_repr() {
+        assert_eq!(
+            check(
+                quote! { as u8 },
+                quote! { enum M { READ, #[mask] ALL, WRITE } }
+            ),
+            Some("`#[mask]` should have a `#[repr(...)]` giving its bits".into())
+        );
+        assert_eq!(
+            check(quote! { as u8 }, quote! { enum M { READ, #[alias] R } }),
+            Some("`#[alias]` should have a `#[repr(...)]` giving its bits".into())
+        );
+        assert_eq!(
+            check(
+                quote! { as u8 },
+                quote! { enum M { READ, WRITE, #[mask] #[repr(0b11)] ALL } }
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn fixed_item_clashes() {
+        assert_eq!(
+            check(
+                quote! { as u8, method_prefix = "from_" },
+                quote! { enum E { Name } }
+            ),
+            Some("`Name` would generate `from_name`, which clashes with a generated method".into())
+        );
+        assert_eq!(
+            check(
+                quote! { as u8, method_prefix = "" },
+                quote! { enum E { Bits } }
+            ),
+            Some("`Bits` would generate `bits`, which clashes with a generated method".into())
+        );
+        assert_eq!(
+            check(
+                quote! { as u8, skip(with_and_without) },
+                quote! { enum E { #[method = "with"] A } }
+            ),
+            None
+        );
+        assert_eq!(
+            check(
+                quote! { as u8 },
+                quote! { enum E { ReadWrite, READ_WRITE } }
+            ),
+            Some("`READ_WRITE` and `ReadWrite` would both generate `READ_WRITE`".into())
+        );
+    }
+}
@@ -0,0 +1,149 @@
+mod context;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::ItemEnum;
+
+use context::{Args, BitfieldEnumCtx};
+
+fn impl_bitfield_enum(ctx: BitfieldEnumCtx) -> TokenStream {
+    let type_name = &ctx.ident;
+    let repr_type = &*ctx.repr_type;
+    let visibility = &ctx.vis;
+    let struct_def = quote! {
+        struct #type_name(#repr_type);
+    };
+    let skips = |name| ctx.options.skips(name);
+    let constant_values = ctx.constant_values();
+    let has_methods = if skips("has_methods") {
+        vec![]
+    } else {
+        ctx.has_methods()
+    };
+    let setter_methods = if skips("setter_methods") {
+        vec![]
+    } else {
+        ctx.setter_methods()
+    };
+    let with_and_without = (!skips("with_and_without")).then(|| ctx.with_and_without());
+    let impl_iter_variants = ctx.impl_iter_variants();
+    let flags_table = ctx.flags_table();
+    let name_lookups = ctx.name_lookups();
+    let decompose = ctx.decompose();
+    let impl_try_from_str = ctx.impl_try_from_str();
+    let bits_and_bytes = ctx.bits_and_bytes();
+    let impl_from = (!skips("From")).then(|| ctx.impl_from());
+    let impl_deref = (!skips("Deref")).then(|| ctx.impl_deref());
+    // DerefMut requires Deref.
+    let impl_deref_mut = (!skips("Deref") && !skips("DerefMut")).then(|| ctx.impl_deref_mut());
+    let impl_bitfield_enum_trait = ctx.impl_bitfield_enum_trait();
+    let impl_bitand = ctx.impl_bitand();
+    let impl_bitor = ctx.impl_bitor();
+    let impl_repr_ops = (!skips("repr_ops")).then(|| ctx.impl_repr_ops());
+    let derives = ctx.derives();
+    let impl_ord = ctx.impl_ord();
+    let subset_comparisons = (!skips("subset_comparisons")).then(|| ctx.subset_comparisons());
+    let c_header = ctx.c_header();
+    let flag_enum = ctx.options.flag_enum.then(|| ctx.flag_enum());
+    let impl_debug = (!skips("Debug")).then(|| ctx.impl_debug());
+    let impl_not = ctx.impl_not();
+    let impl_serde = ctx.impl_serde();
+    let impl_schemars = ctx.impl_schemars();
+    let impl_clap = ctx.impl_clap();
+    let derive_zerocopy = ctx.derive_zerocopy();
+    let impl_bytemuck = ctx.impl_bytemuck();
+    let impl_arbitrary = ctx.impl_arbitrary();
+    let impl_proptest = ctx.impl_proptest();
+    let impl_rand = ctx.impl_rand();
+    let atomic_type = ctx.atomic_type();
+
+    quote! {
+        #derives
+        #derive_zerocopy
+        #[repr(transparent)]
+        #visibility #struct_def
+
+        impl #type_name {
+            #(#constant_values)*
+
+            #(#has_methods)*
+
+            #(#setter_methods)*
+
+            #with_and_without
+
+            #impl_iter_variants
+
+            #flags_table
+
+            #name_lookups
+
+            #decompose
+
+            #bits_and_bytes
+
+            #subset_comparisons
+
+            #c_header
+        }
+
+        #flag_enum
+
+        #impl_bitfield_enum_trait
+
+        #impl_try_from_str
+
+        #impl_from
+
+        #impl_deref
+
+        #impl_deref_mut
+
+        #impl_bitand
+
+        #impl_bitor
+
+        #impl_repr_ops
+
+        #impl_not
+
+        #impl_ord
+
+        #impl_debug
+
+        #impl_serde
+
+        #impl_schemars
+
+        #impl_clap
+
+        #impl_bytemuck
+
+        #impl_arbitrary
+
+        #impl_proptest
+
+        #impl_rand
+
+        #atomic_type
+    }
+    .into()
+}
+
+#[proc_macro_attribute]
+pub fn bitfield_enum(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let Args { repr_type, options } = match syn::parse(attr) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let enum_def: ItemEnum = syn::parse(item).expect("failed to parse input");
+    let ctx = BitfieldEnumCtx {
+        repr_type,
+        enum_def,
+        options,
+    };
+    if let Err(err) = ctx.check_identifiers() {
+        return err.to_compile_error().into();
+    }
+    impl_bitfield_enum(ctx)
+}
@@ -3,7 +3,7 @@ use std::{convert::Infallible, io, os::unix::process::CommandExt, process::Comma
 #[macro_use]
 extern crate classic_bitfield;
 
-#[bitfield_enum(as u8)]
+#[bitfield_enum(as u8, flag_enum)]
 pub(crate) enum TestEnum {
     /// first option
     ONE,
@@ -16,6 +16,134 @@ pub(crate) enum TestEnum {
     ONE_AND_THREE,
 }
 
+#[bitfield_enum(as u16, separator = ",", serde = "names", atomic, no_ord, c_header)]
+pub(crate) enum Permissions {
+    /// Permission to run executables or list directories
+    EXECUTE,
+    /// Permssion to write to the file
+    WRITE,
+    /// Permission to read to the file
+    READ,
+}
+
+#[bitfield_enum(bits = 256, serde = "pipe_separated", flag_enum)]
+pub(crate) enum Wide {
+    LOW,
+    NEXT,
+    /// The most significant bit
+    #[repr([0, 0, 0, 1 << 63])]
+    TOP,
+}
+
+#[bitfield_enum(as u8, flag_enum)]
+pub(crate) enum Access {
+    Read,
+    ReadWrite,
+    exec_only,
+}
+
+#[bitfield_enum(as u8, method_prefix = "is_", serde = "names")]
+pub(crate) enum State {
+    Ready,
+    Running,
+    #[method = "has_failed"]
+    Failed,
+    /// Replaced by `Failed`
+    #[deprecated]
+    Crashed,
+}
+
+#[bitfield_enum(
+    as u8,
+    serde = "names",
+    atomic,
+    skip(
+        Debug,
+        Deref,
+        DerefMut,
+        From,
+        repr_ops,
+        has_methods,
+        with_and_without,
+        subset_comparisons
+    )
+)]
+pub(crate) enum Minimal {
+    FIRST,
+    SECOND,
+}
+
+impl std::fmt::Debug for Minimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Minimal({:?})", self.names_of_set_variants())
+    }
+}
+
+#[bitfield_enum(as u8, strict)]
+pub(crate) enum Strict {
+    LEFT,
+    RIGHT,
+}
+
+#[bitfield_enum(as u8, serde = "names", minimal_names)]
+pub(crate) enum Rw {
+    READ,
+    WRITE,
+    #[repr(0b11)]
+    READ_AND_WRITE,
+    EXEC,
+}
+
+#[bitfield_enum(as u16, serde = "names", flag_enum)]
+pub(crate) enum Mode {
+    OWNER_READ,
+    OWNER_WRITE,
+    OTHER_READ,
+    #[alias]
+    #[repr(0b1)]
+    READ,
+    /// Every permission bit
+    #[mask]
+    #[repr(0o777)]
+    PERM_MASK,
+}
+
+#[bitfield_enum(as u8)]
+pub(crate) enum Cover {
+    #[repr(0b000111)]
+    LOW,
+    #[repr(0b111000)]
+    HIGH,
+    #[repr(0b011110)]
+    MIDDLE,
+}
+
+#[bitfield_enum(as u8, flag_enum)]
+pub(crate) enum Shifted {
+    X,
+    #[repr(1 << 3)]
+    Y,
+    #[repr(Shifted::X.0 | Shifted::Y.0)]
+    BOTH,
+}
+
+#[bitfield_enum(as u128, c_header)]
+pub(crate) enum Big {
+    LOW,
+    #[repr(1 << 100)]
+    HIGH,
+}
+
+const O_EXEC: u8 = 1 << 5;
+
+/// Single-bit reprs which aren't literals are fine without `flag_enum`.
+#[bitfield_enum(as u8)]
+pub(crate) enum Open {
+    READ,
+    #[repr(O_EXEC)]
+    EXEC,
+}
+
 fn main() -> io::Result<Infallible> {
     Err(Command::new("cargo").arg("test").exec())
 }
@@ -117,6 +245,28 @@ mod tests {
         let subject: T = serde_json::from_str(r#"{"v": ["ONE", "THREE"]}"#).expect("deserialize");
         assert!(subject.v.has_one_and_three());
     }
+
+    #[test]
+    fn test_serde_names_from_reader() {
+        use test_enum_serde::names;
+        #[derive(Deserialize)]
+        struct T {
+            #[serde(with = "names")]
+            v: TestEnum,
+        }
+        let subject: T =
+            serde_json::from_reader(r#"{"v": ["ONE", "TWO"]}"#.as_bytes()).expect("deserialize");
+        assert_eq!(subject.v, TestEnum::ONE | TestEnum::TWO);
+        // escape sequences can't be borrowed from the input either
+        let subject: T =
+            serde_json::from_str(r#"{"v": ["\u004fNE", "THREE"]}"#).expect("deserialize");
+        assert!(subject.v.has_one_and_three());
+        let error = serde_json::from_reader::<_, T>(r#"{"v": ["FOUR"]}"#.as_bytes())
+            .err()
+            .expect("unknown variant");
+        assert!(error.to_string().contains("unknown variant `FOUR`"));
+    }
+
     #[test]
     fn test_serde_as_stringified_number() {
         use test_enum_serde::numeric_representation;
@@ -138,6 +288,451 @@ mod tests {
         assert!(subject.v.has_one_and_three());
     }
 
+    #[test]
+    fn test_serde_pipe_separated() {
+        use permissions_serde::pipe_separated as comma_separated;
+        use test_enum_serde::pipe_separated;
+        #[derive(Serialize, Deserialize)]
+        struct T {
+            #[serde(with = "pipe_separated")]
+            v: TestEnum,
+            #[serde(with = "comma_separated")]
+            p: Permissions,
+        }
+        let subject = T {
+            v: TestEnum::ONE | TestEnum::TWO | 0b10000,
+            p: Permissions::READ | Permissions::WRITE,
+        };
+        assert_eq!(
+            serde_json::to_string(&subject).expect("serialize"),
+            r#"{"v":"ONE | TWO | 16","p":"WRITE,READ"}"#
+        );
+        let subject: T =
+            serde_json::from_str(r#"{"v": "ONE|three", "p": "read, write"}"#).expect("deserialize");
+        assert!(subject.v.has_one_and_three());
+        assert_eq!(subject.p, Permissions::READ | Permissions::WRITE);
+        let subject: T = serde_json::from_str(r#"{"v": "ONE | 16", "p": 4}"#).expect("deserialize");
+        assert_eq!(subject.v, 0b10001);
+        assert_eq!(subject.p, Permissions::READ);
+        let subject: T = serde_json::from_str(r#"{"v": "", "p": "3"}"#).expect("deserialize");
+        assert_eq!(subject.v, 0);
+        assert_eq!(subject.p, Permissions::EXECUTE | Permissions::WRITE);
+        assert!(serde_json::from_str::<T>(r#"{"v": "ONE | FOUR", "p": ""}"#).is_err());
+    }
+
+    #[test]
+    fn test_serde_pipe_separated_binary() {
+        let subject = Wide::NEXT | Wide::TOP | Wide([0, 0, 1, 0]);
+        let bytes = bincode::serialize(&subject).expect("serialize");
+        assert_eq!(bincode::deserialize::<Wide>(&bytes).unwrap(), subject);
+    }
+
+    #[test]
+    fn test_serde_strict_and_truncating() {
+        use test_enum_serde::numeric_representation::{strict, truncating};
+        #[derive(Serialize, Deserialize)]
+        struct T {
+            #[serde(with = "strict")]
+            strict: TestEnum,
+            #[serde(with = "truncating")]
+            truncating: TestEnum,
+        }
+        let subject: T =
+            serde_json::from_str(r#"{"strict": 5, "truncating": 5}"#).expect("deserialize");
+        assert!(subject.strict.has_one_and_three());
+        assert!(subject.truncating.has_one_and_three());
+        let subject: T =
+            serde_json::from_str(r#"{"strict": 7, "truncating": 255}"#).expect("deserialize");
+        assert_eq!(subject.truncating, TestEnum::all_set());
+        let error = serde_json::from_str::<T>(r#"{"strict": 255, "truncating": 0}"#)
+            .err()
+            .expect("undefined bits");
+        assert!(error
+            .to_string()
+            .starts_with("invalid value: integer 255 (undefined bits 0b11111000)"));
+    }
+
+    #[test]
+    fn test_serde_on_type() {
+        use std::collections::HashMap;
+
+        let subject = vec![Permissions::READ | Permissions::WRITE, Permissions::EXECUTE];
+        assert_eq!(
+            serde_json::to_string(&subject).expect("serialize"),
+            r#"[["WRITE","READ"],["EXECUTE"]]"#
+        );
+        let subject: HashMap<String, Option<Permissions>> =
+            serde_json::from_str(r#"{"a": ["READ"], "b": null}"#).expect("deserialize");
+        assert_eq!(subject["a"], Some(Permissions::READ));
+        assert_eq!(subject["b"], None);
+    }
+
+    #[test]
+    fn test_serde_in_function_body() {
+        #[bitfield_enum(as u8, serde_module = "local_serde")]
+        enum Local {
+            A,
+            B,
+        }
+
+        #[derive(Serialize, Deserialize)]
+        struct T {
+            #[serde(with = "local_serde::names")]
+            names: Local,
+            #[serde(with = "local_serde::numeric_representation::strict")]
+            number: Local,
+        }
+        let subject = T {
+            names: Local::A | Local::B,
+            number: Local::B,
+        };
+        let serialized = serde_json::to_string(&subject).expect("serialize");
+        assert_eq!(serialized, r#"{"names":["A","B"],"number":2}"#);
+        let subject: T = serde_json::from_str(&serialized).expect("deserialize");
+        assert_eq!(subject.names, Local::A | Local::B);
+        assert_eq!(subject.number, Local::B);
+    }
+
+    #[test]
+    fn test_json_schema() {
+        use serde_json::json;
+
+        let schema = schemars::schema_for!(TestEnum);
+        assert_eq!(schema.get("type"), Some(&json!("integer")));
+        assert_eq!(schema.get("minimum"), Some(&json!(0)));
+        assert_eq!(schema.get("maximum"), Some(&json!(255)));
+
+        let schema = schemars::schema_for!(Permissions);
+        assert_eq!(schema.get("type"), Some(&json!("array")));
+        assert_eq!(schema.get("uniqueItems"), Some(&json!(true)));
+        assert_eq!(
+            schema.get("items"),
+            Some(&json!({
+                "oneOf": [
+                    {
+                        "const": "EXECUTE",
+                        "description": "Permission to run executables or list directories"
+                    },
+                    { "const": "WRITE", "description": "Permssion to write to the file" },
+                    { "const": "READ", "description": "Permission to read to the file" },
+                ]
+            }))
+        );
+    }
+
+    #[test]
+    fn test_clap_value_parser() {
+        use clap::{value_parser, Arg, ArgAction, Command};
+
+        let command = Command::new("test").arg(
+            Arg::new("perm")
+                .long("perm")
+                .action(ArgAction::Append)
+                .value_parser(value_parser!(Permissions)),
+        );
+        let matches = command
+            .clone()
+            .try_get_matches_from(["test", "--perm", "read,write", "--perm", "EXECUTE"])
+            .expect("parse arguments");
+        let subject: Permissions = matches
+            .get_many::<Permissions>("perm")
+            .expect("perm")
+            .copied()
+            .collect();
+        assert_eq!(subject, Permissions::all_set());
+        let error = command
+            .clone()
+            .try_get_matches_from(["test", "--perm", "read,delete"])
+            .expect_err("unknown flag");
+        assert_eq!(error.kind(), clap::error::ErrorKind::InvalidValue);
+        let help = command.clone().render_long_help().to_string();
+        assert!(help.contains("- EXECUTE: Permission to run executables or list directories"));
+        assert!(help.contains("- READ:    Permission to read to the file"));
+    }
+
+    #[test]
+    fn test_clap_repeated_arg() {
+        use clap::{Arg, Command};
+
+        let command =
+            Command::new("test").arg(Permissions::clap_arg(Arg::new("perm").long("perm")));
+        let matches = command
+            .clone()
+            .try_get_matches_from(["test", "--perm", "read", "--perm", "write,execute"])
+            .expect("parse arguments");
+        assert_eq!(
+            Permissions::from_clap_matches(&matches, "perm"),
+            Permissions::all_set()
+        );
+        let matches = command
+            .try_get_matches_from(["test"])
+            .expect("parse arguments");
+        assert_eq!(
+            Permissions::from_clap_matches(&matches, "perm"),
+            Permissions::empty()
+        );
+    }
+
+    #[test]
+    fn test_zero_copy() {
+        use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+        static_assertions::assert_eq_size!(TestEnum, u8);
+        static_assertions::assert_eq_size!(Permissions, u16);
+
+        #[derive(FromBytes, IntoBytes, Immutable, KnownLayout)]
+        #[repr(C)]
+        struct Header {
+            flags: Permissions,
+            other: u16,
+        }
+
+        let bytes = [0b110u16.to_ne_bytes(), 42u16.to_ne_bytes()].concat();
+        let header = Header::ref_from_bytes(&bytes).expect("cast");
+        assert_eq!(header.flags, Permissions::READ | Permissions::WRITE);
+        assert_eq!(header.other, 42);
+        assert_eq!(header.as_bytes(), &bytes);
+
+        let values: &[TestEnum] = bytemuck::cast_slice(&[1u8, 5]);
+        assert_eq!(values, &[TestEnum::ONE, TestEnum::ONE_AND_THREE]);
+        assert_eq!(bytemuck::bytes_of(&TestEnum::THREE), &[4]);
+    }
+
+    #[test]
+    fn test_arbitrary() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let mut u = Unstructured::new(&[0b1000_0101, 0b11]);
+        assert_eq!(TestEnum::arbitrary(&mut u).expect("arbitrary"), 0b1000_0101);
+        assert_eq!(
+            Permissions::arbitrary(&mut Unstructured::new(&[6, 0])).expect("arbitrary"),
+            Permissions::READ | Permissions::WRITE
+        );
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_proptest_named_variants(value in proptest::arbitrary::any::<TestEnum>()) {
+            proptest::prop_assert_eq!(value & !TestEnum::all_set().0, 0);
+        }
+    }
+
+    #[test]
+    fn test_proptest_shrinks_towards_fewer_flags() {
+        use proptest::strategy::{Strategy, ValueTree};
+        use proptest::test_runner::TestRunner;
+
+        let mut runner = TestRunner::deterministic();
+        let mut tree = proptest::arbitrary::any::<Permissions>()
+            .new_tree(&mut runner)
+            .expect("new tree");
+        while tree.simplify() {}
+        assert_eq!(tree.current(), 0);
+
+        let strategy = proptest::arbitrary::any_with::<Permissions>(true);
+        let values: Vec<_> = (0..100)
+            .map(|_| strategy.new_tree(&mut runner).expect("new tree").current())
+            .collect();
+        assert!(values
+            .iter()
+            .any(|value| *value & !Permissions::all_set().0 != 0));
+        let mut tree = strategy.new_tree(&mut runner).expect("new tree");
+        while tree.simplify() {}
+        assert_eq!(tree.current(), 0);
+    }
+
+    #[test]
+    fn test_rand_distribution() {
+        use rand::{rngs::mock::StepRng, Rng};
+
+        // `gen::<bool>()` is true for every value with the top bit set
+        let mut rng = StepRng::new(u64::MAX, 0);
+        assert_eq!(rng.gen::<Permissions>(), Permissions::all_set());
+        let mut rng = StepRng::new(0, 0);
+        assert_eq!(rng.gen::<TestEnum>(), 0);
+    }
+
+    #[test]
+    fn test_bits_and_bytes() {
+        assert_eq!(TestEnum::ONE_AND_THREE.bits(), 5);
+        assert_eq!(TestEnum::from_bits(3), Some(TestEnum::ONE | TestEnum::TWO));
+        assert_eq!(TestEnum::from_bits(8), None);
+        assert_eq!(TestEnum::from_bits_truncate(0b1001), TestEnum::ONE);
+
+        let subject = Permissions::READ | 0x100;
+        assert_eq!(subject.to_le_bytes(), [4, 1]);
+        assert_eq!(subject.to_be_bytes(), [1, 4]);
+        assert_eq!(Permissions::from_le_bytes([4, 1]), subject);
+        assert_eq!(Permissions::from_be_bytes([1, 4]), subject);
+        assert_eq!(Permissions::from_le_bytes_checked([4, 1]), None);
+        assert_eq!(
+            Permissions::from_be_bytes_checked([0, 4]),
+            Some(Permissions::READ)
+        );
+        assert_eq!(
+            Permissions::from_le_bytes_truncate([4, 1]),
+            Permissions::READ
+        );
+        assert_eq!(
+            Permissions::from_be_bytes_truncate([1, 4]),
+            Permissions::READ
+        );
+
+        let mut buffer = vec![];
+        subject.write_to_be(&mut buffer).expect("write");
+        Permissions::WRITE.write_to_le(&mut buffer).expect("write");
+        assert_eq!(buffer, [1, 4, 2, 0]);
+        let mut reader = buffer.as_slice();
+        assert_eq!(
+            Permissions::read_from_be(&mut reader).expect("read"),
+            subject
+        );
+        assert_eq!(
+            Permissions::read_from_le(&mut reader).expect("read"),
+            Permissions::WRITE
+        );
+        assert!(Permissions::read_from_le(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_atomic() {
+        use std::sync::{atomic::Ordering, Arc};
+
+        let shared = Arc::new(AtomicPermissions::new(Permissions::READ));
+        let handles: Vec<_> = [Permissions::WRITE, Permissions::EXECUTE]
+            .into_iter()
+            .map(|flag| {
+                let shared = Arc::clone(&shared);
+                std::thread::spawn(move || shared.fetch_insert(flag, Ordering::SeqCst))
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("join");
+        }
+        assert_eq!(shared.load(Ordering::SeqCst), Permissions::all_set());
+        assert_eq!(
+            shared.fetch_remove(Permissions::EXECUTE, Ordering::SeqCst),
+            Permissions::all_set()
+        );
+        assert_eq!(
+            shared.fetch_xor(Permissions::READ, Ordering::SeqCst),
+            Permissions::READ | Permissions::WRITE
+        );
+        assert_eq!(
+            shared.compare_exchange(
+                Permissions::READ,
+                Permissions::EXECUTE,
+                Ordering::SeqCst,
+                Ordering::SeqCst
+            ),
+            Err(Permissions::WRITE)
+        );
+        assert_eq!(
+            shared.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |value| Some(
+                value.with(Permissions::READ)
+            )),
+            Ok(Permissions::WRITE)
+        );
+        shared.store(Permissions::EXECUTE, Ordering::SeqCst);
+        assert_eq!(format!("{shared:?}"), "Permissions::EXECUTE");
+    }
+
+    #[test]
+    fn test_generic_bitfield_enum() {
+        use classic_bitfield::BitfieldEnum;
+
+        fn describe<F: BitfieldEnum>(flags: F) -> String {
+            let names: Vec<_> = flags.iter_set().map(|(name, _)| name).collect();
+            names.join(" | ")
+        }
+
+        assert_eq!(
+            describe(TestEnum::ONE_AND_THREE),
+            "ONE | THREE | ONE_AND_THREE"
+        );
+        assert_eq!(describe(Permissions::READ), "READ");
+
+        fn raw<F: BitfieldEnum<Repr = u16>>(flags: F) -> u16 {
+            flags.bits()
+        }
+        assert_eq!(raw(Permissions::READ | Permissions::WRITE), 6);
+
+        let mut subject = <TestEnum as BitfieldEnum>::empty();
+        assert!(subject.is_empty());
+        subject.insert(TestEnum::ONE);
+        subject.toggle(TestEnum::ONE | TestEnum::TWO);
+        assert_eq!(subject, TestEnum::TWO);
+        subject.set(TestEnum::THREE, true);
+        subject.remove(TestEnum::TWO);
+        assert_eq!(subject, TestEnum::THREE);
+        assert!(subject.intersects(TestEnum::ONE_AND_THREE));
+        assert!(!subject.contains(TestEnum::ONE_AND_THREE));
+        assert_eq!(subject.complement(), TestEnum::ONE | TestEnum::TWO);
+        assert_eq!(
+            TestEnum::ONE.symmetric_difference(TestEnum::ONE_AND_THREE),
+            TestEnum::THREE
+        );
+        assert!(<TestEnum as BitfieldEnum>::all().is_all());
+        assert_eq!(<TestEnum as BitfieldEnum>::from_bits(8), None);
+        assert_eq!(
+            <TestEnum as BitfieldEnum>::from_bits_truncate(9),
+            TestEnum::ONE
+        );
+    }
+
+    #[test]
+    fn test_subset_comparisons() {
+        use std::cmp::Ordering;
+
+        static_assertions::assert_impl_all!(TestEnum: PartialOrd, Ord);
+        static_assertions::assert_not_impl_any!(Permissions: PartialOrd, Ord, PartialOrd<u16>);
+
+        let subject = TestEnum::ONE;
+        // numerically smaller, but not a subset
+        assert!(subject < TestEnum::TWO | TestEnum::THREE);
+        assert!(!subject.is_subset_of(TestEnum::TWO | TestEnum::THREE));
+        assert!(subject.is_disjoint(TestEnum::TWO | TestEnum::THREE));
+        assert_eq!(subject.subset_cmp(TestEnum::TWO | TestEnum::THREE), None);
+
+        assert!(subject.is_subset_of(TestEnum::ONE_AND_THREE));
+        assert!(!subject.is_disjoint(TestEnum::ONE_AND_THREE));
+        assert_eq!(
+            subject.subset_cmp(TestEnum::ONE_AND_THREE),
+            Some(Ordering::Less)
+        );
+        assert!(TestEnum::ONE_AND_THREE.is_superset_of(subject));
+        assert_eq!(
+            TestEnum::ONE_AND_THREE.subset_cmp(subject),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(subject.subset_cmp(subject), Some(Ordering::Equal));
+        assert!(Permissions::READ.is_subset_of(Permissions::all_set()));
+    }
+
+    #[test]
+    fn test_c_header() {
+        assert_eq!(
+            Permissions::c_header(),
+            "/* Generated from the Permissions bitfield_enum. */
+#ifndef PERMISSIONS_H
+#define PERMISSIONS_H
+
+#include <stdint.h>
+
+typedef uint16_t Permissions;
+
+#define PERMISSIONS_EXECUTE ((Permissions)0x1)
+#define PERMISSIONS_WRITE ((Permissions)0x2)
+#define PERMISSIONS_READ ((Permissions)0x4)
+
+#endif /* PERMISSIONS_H */
+"
+        );
+        assert!(Big::c_header().contains(
+            "#define BIG_LOW ((Big)0x1)\n#define BIG_HIGH (((Big)0x1000000000ULL << 64) | 0x0ULL)\n"
+        ));
+    }
+
     #[test]
     fn test_list_names_and_values() {
         assert_eq!(
@@ -165,4 +760,256 @@ mod tests {
         let subject = TestEnum::ONE | TestEnum::TWO;
         assert_eq!(subject.names_of_set_variants(), &["ONE", "TWO"]);
     }
+
+    #[test]
+    fn array_repr() {
+        let subject = Wide::LOW | Wide::TOP;
+        assert!(subject.has_low());
+        assert!(!subject.has_next());
+        assert!(subject.has_top());
+        assert_eq!(subject.bits(), [1, 0, 0, 1 << 63]);
+        assert_eq!(!subject & Wide::all_set(), Wide::NEXT);
+        assert!(Wide::TOP > Wide::LOW | Wide::NEXT);
+        assert_eq!(format!("{subject:?}"), "Wide::LOW | Wide::TOP");
+        assert_eq!(
+            format!("{:?}", Wide([0, 4, 0, 0])),
+            "Wide(0x40000000000000000)"
+        );
+        assert_eq!(Wide::from_bits([0, 4, 0, 0]), None);
+        assert_eq!(
+            Wide::from_bits_truncate([3, 4, 0, 0]),
+            Wide::LOW | Wide::NEXT
+        );
+        assert_eq!(Wide::from_be_bytes(subject.to_be_bytes()), subject);
+        assert_eq!(subject.to_le_bytes()[0], 1);
+        assert_eq!(subject.to_be_bytes()[0], 0x80);
+    }
+
+    #[test]
+    fn array_repr_serde() {
+        let subject = Wide::TOP | Wide([0, 0, 1, 0]);
+        let json = serde_json::to_string(&subject).unwrap();
+        assert_eq!(json, r#""TOP | 0x100000000000000000000000000000000""#);
+        assert_eq!(serde_json::from_str::<Wide>(&json).unwrap(), subject);
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Numeric {
+            #[serde(with = "wide_serde::numeric_representation")]
+            value: Wide,
+        }
+        let numeric = Numeric {
+            value: Wide::NEXT | Wide::TOP,
+        };
+        let json = serde_json::to_string(&numeric).unwrap();
+        assert_eq!(json, r#"{"value":[2,0,0,9223372036854775808]}"#);
+        assert_eq!(serde_json::from_str::<Numeric>(&json).unwrap(), numeric);
+        assert_eq!(
+            serde_json::from_str::<Numeric>(
+                r#"{"value":"0x8000000000000000000000000000000000000000000000000000000000000002"}"#
+            )
+            .unwrap(),
+            numeric,
+        );
+    }
+
+    #[test]
+    fn mixed_case_variants() {
+        let subject = Access::READ_WRITE | Access::EXEC_ONLY;
+        assert!(subject.has_read_write());
+        assert!(subject.has_exec_only());
+        assert!(!subject.has_read());
+        assert_eq!(
+            format!("{subject:?}"),
+            "Access::READ_WRITE | Access::EXEC_ONLY"
+        );
+        assert_eq!(
+            Access::variant_names(),
+            &["READ", "READ_WRITE", "EXEC_ONLY"]
+        );
+    }
+
+    #[test]
+    fn renamed_methods() {
+        let subject = State::READY | State::FAILED;
+        assert!(subject.is_ready());
+        assert!(!subject.is_running());
+        assert!(subject.has_failed());
+        assert_eq!(format!("{subject:?}"), "State::READY | State::FAILED");
+        let json = serde_json::to_string(&subject).unwrap();
+        assert_eq!(json, r#"["READY","FAILED"]"#);
+        assert_eq!(serde_json::from_str::<State>(&json).unwrap(), subject);
+    }
+
+    #[test]
+    fn skipped_impls() {
+        let subject = Minimal::FIRST | Minimal::SECOND;
+        assert_eq!(format!("{subject:?}"), r#"Minimal(["FIRST", "SECOND"])"#);
+        assert_eq!(subject.bits(), 3);
+        let json = serde_json::to_string(&Minimal::SECOND).unwrap();
+        assert_eq!(json, r#"["SECOND"]"#);
+        assert_eq!(
+            serde_json::from_str::<Minimal>(&json).unwrap(),
+            Minimal::SECOND
+        );
+    }
+
+    #[test]
+    fn strict_mode() {
+        use std::ops::{BitAnd, BitOr, Deref};
+
+        static_assertions::assert_not_impl_any!(
+            Strict: BitAnd<u8>,
+            BitOr<u8>,
+            PartialEq<u8>,
+            PartialOrd<u8>,
+            Deref,
+            From<u8>,
+            Into<u8>
+        );
+        static_assertions::assert_not_impl_any!(u8: BitAnd<Strict>, BitOr<Strict>);
+        let subject = Strict::LEFT | Strict::RIGHT;
+        assert_eq!(subject.bits(), 3);
+        assert_eq!(Strict::from_bits(1), Some(Strict::LEFT));
+        assert_eq!(Strict::from_bits_retain(4).bits(), 4);
+    }
+
+    #[test]
+    fn setter_methods() {
+        let mut subject = Permissions::empty().with_read().with_write();
+        assert_eq!(subject, Permissions::READ | Permissions::WRITE);
+        assert_eq!(subject.without_write(), Permissions::READ);
+        subject.set_execute(true);
+        subject.set_read(false);
+        assert_eq!(subject, Permissions::EXECUTE | Permissions::WRITE);
+        subject.toggle_write();
+        subject.toggle_read();
+        assert_eq!(subject, Permissions::EXECUTE | Permissions::READ);
+        let mut wide = Wide::empty().with_top();
+        wide.toggle_low();
+        assert_eq!(wide, Wide::LOW | Wide::TOP);
+    }
+
+    #[test]
+    fn flag_enum() {
+        fn describe(flag: TestEnumFlag) -> &'static str {
+            match flag {
+                TestEnumFlag::One => "one",
+                TestEnumFlag::Two => "two",
+                TestEnumFlag::Three => "three",
+            }
+        }
+
+        let subject = TestEnum::ONE_AND_THREE;
+        let flags: Vec<_> = subject.flags().collect();
+        assert_eq!(flags, [TestEnumFlag::One, TestEnumFlag::Three]);
+        assert_eq!(
+            flags.into_iter().map(describe).collect::<Vec<_>>(),
+            ["one", "three"]
+        );
+        assert_eq!(TestEnum::from(TestEnumFlag::Two), TestEnum::TWO);
+        assert_eq!(TestEnumFlag::try_from(TestEnum::TWO), Ok(TestEnumFlag::Two));
+        assert_eq!(TestEnumFlag::try_from(subject), Err(subject));
+        assert_eq!(
+            AccessFlag::ALL,
+            &[
+                AccessFlag::Read,
+                AccessFlag::ReadWrite,
+                AccessFlag::ExecOnly
+            ]
+        );
+        assert_eq!(ShiftedFlag::ALL, &[ShiftedFlag::X, ShiftedFlag::Y]);
+        assert_eq!(Shifted::Y.flags().collect::<Vec<_>>(), [ShiftedFlag::Y]);
+        assert_eq!(Shifted::FLAGS[1].bit, Some(3));
+        assert_eq!(WideFlag::try_from(Wide::TOP), Ok(WideFlag::Top));
+        assert_eq!(Open::EXEC.bits(), 32);
+    }
+
+    #[test]
+    fn flags_table() {
+        let one = TestEnum::FLAGS[0];
+        assert_eq!(one.name, "ONE");
+        assert_eq!(one.value, TestEnum::ONE);
+        assert_eq!(one.bit, Some(0));
+        assert!(!one.composite);
+        assert!(!one.deprecated);
+        assert_eq!(one.doc, "first option");
+        let combo = TestEnum::FLAGS[3];
+        assert_eq!(combo.bit, None);
+        assert!(combo.composite);
+        assert_eq!(Wide::FLAGS[2].bit, Some(255));
+        let crashed = State::FLAGS[3];
+        assert!(crashed.deprecated);
+        assert_eq!(crashed.doc, "Replaced by `Failed`");
+    }
+
+    #[test]
+    fn name_lookups() {
+        assert_eq!(TestEnum::from_name("TWO"), Some(TestEnum::TWO));
+        assert_eq!(TestEnum::from_name("two"), None);
+        assert_eq!(TestEnum::from_name_ignore_case("two"), Some(TestEnum::TWO));
+        assert_eq!(TestEnum::ONE_AND_THREE.name(), Some("ONE_AND_THREE"));
+        assert_eq!((TestEnum::ONE | TestEnum::TWO).name(), None);
+        assert_eq!(TestEnum::try_from("ONE"), Ok(TestEnum::ONE));
+        let err = TestEnum::try_from("FOUR").unwrap_err();
+        assert_eq!(err.to_string(), r#"no variant is named "FOUR""#);
+        assert_eq!(Wide::TOP.name(), Some("TOP"));
+    }
+
+    #[test]
+    fn decompose() {
+        let (parts, unnamed) = (Rw::READ | Rw::WRITE | Rw::EXEC | Rw(16)).decompose();
+        assert_eq!(parts.collect::<Vec<_>>(), [Rw::READ_AND_WRITE, Rw::EXEC]);
+        assert_eq!(unnamed, 16);
+        let (parts, unnamed) = (TestEnum::ONE | TestEnum::TWO | TestEnum::THREE).decompose();
+        assert_eq!(
+            parts.collect::<Vec<_>>(),
+            [TestEnum::TWO, TestEnum::ONE_AND_THREE]
+        );
+        assert_eq!(unnamed, 0);
+        let (parts, unnamed) = Cover(0b111111).decompose();
+        assert_eq!(parts.collect::<Vec<_>>(), [Cover::LOW, Cover::HIGH]);
+        assert_eq!(unnamed, 0);
+        let (parts, unnamed) = (Cover::MIDDLE | Cover(0b1000_0000)).decompose();
+        assert_eq!(parts.collect::<Vec<_>>(), [Cover::MIDDLE]);
+        assert_eq!(unnamed, 0b1000_0000);
+
+        let json = serde_json::to_string(&(Rw::READ_AND_WRITE | Rw::EXEC)).unwrap();
+        assert_eq!(json, r#"["READ_AND_WRITE","EXEC"]"#);
+        assert_eq!(
+            serde_json::from_str::<Rw>(&json).unwrap(),
+            Rw::READ_AND_WRITE | Rw::EXEC
+        );
+        let json = serde_json::to_string(&Rw::WRITE).unwrap();
+        assert_eq!(json, r#"["WRITE"]"#);
+    }
+
+    #[test]
+    fn masks_and_aliases() {
+        assert_eq!(
+            Mode::variant_names(),
+            &["OWNER_READ", "OWNER_WRITE", "OTHER_READ"]
+        );
+        assert_eq!(Mode::all_set().bits(), 0b111);
+        assert_eq!(Mode::all_variant_pairs().len(), 5);
+        assert!(Mode::OWNER_READ.has_read());
+        assert!(!Mode(0o700).has_perm_mask());
+        assert!(Mode::PERM_MASK.has_perm_mask());
+        assert_eq!(format!("{:?}", Mode::READ), "Mode::OWNER_READ");
+        assert_eq!(Mode::READ.names_of_set_variants(), ["OWNER_READ"]);
+        assert_eq!(ModeFlag::ALL.len(), 3);
+        assert_eq!(Mode::from_name("PERM_MASK"), Some(Mode::PERM_MASK));
+
+        assert_eq!(Mode::FLAGS.len(), 3);
+        let mask = Mode::ALL_FLAGS[4];
+        assert!(mask.mask && !mask.alias);
+        assert_eq!(mask.doc, "Every permission bit");
+        assert!(Mode::ALL_FLAGS[3].alias);
+
+        let json = serde_json::to_string(&Mode::READ).unwrap();
+        assert_eq!(json, r#"["OWNER_READ"]"#);
+        assert_eq!(
+            serde_json::from_str::<Mode>(r#"["READ", "OTHER_READ"]"#).unwrap(),
+            Mode::OWNER_READ | Mode::OTHER_READ
+        );
+    }
 }